@@ -52,6 +52,10 @@ pub async fn main_loop(mut runner: AsyncRunner) -> anyhow::Result<()> {
                 println!("we got a 'normal' eof");
                 break;
             }
+            // a line couldn't be decoded (only when `set_report_bad_lines` is enabled)
+            Status::BadLine { raw, error } => {
+                println!("bad line: {} ({:?})", error, String::from_utf8_lossy(&raw));
+            }
         }
     }
 
@@ -71,10 +75,22 @@ async fn handle_message(msg: messages::Commands<'_>) {
         // this crate add them.
         Raw(_) => {}
 
+        // Your own message types, if you've registered them with a `Registry`
+        Custom(_) => {}
+
         // These happen when you initially connect
         IrcReady(_) => {}
         Ready(_) => {}
         Cap(_) => {}
+        Welcome(_) => {}
+        Motd(_) => {}
+
+        // These are the users on a channel, when you join it
+        Names(_) => {}
+        EndOfNames(_) => {}
+
+        // The server didn't understand something you sent
+        UnknownCommand(msg) => eprintln!("{}", msg),
 
         // and a bunch of other messages you may be interested in
         ClearChat(_) => {}
@@ -131,7 +131,7 @@ impl Bot {
                 // stop if we're stopping
                 Status::Quit | Status::Eof => break,
                 // ignore the rest
-                Status::Message(..) => continue,
+                Status::Message(..) | Status::BadLine { .. } => continue,
             }
         }
 
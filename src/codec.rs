@@ -0,0 +1,405 @@
+//! Codecs for framing Trovo (IRC) messages over a byte stream
+//!
+//! These let you use the runtime's framing utilities (e.g. `Framed`) rather than the [Decoder](crate::Decoder) and [Encoder](crate::Encoder) types, which own their reader or writer.
+//!
+//! The [IrcCodec] decodes complete lines from a `BytesMut`, keeping any partial line until more data arrives. Any [Encodable] type can be encoded with it.
+//!
+//! | codec crate                                                                 | features required               |
+//! | --------------------------------------------------------------------------- | ------------------------------- |
+//! | [`tokio-util`](https://docs.rs/tokio-util/0.3/tokio_util/codec/index.html)  | `"tokio-util"`                  |
+//! | [`asynchronous-codec`](https://docs.rs/asynchronous-codec/0.5/)             | `"asynchronous-codec"`          |
+use crate::{
+    irc::ParseMode, messages::Commands, DecodeError, Encodable, FromIrcMessage, IrcMessage,
+    MaybeOwned,
+};
+use std::{io::Write, marker::PhantomData, ops::Deref};
+
+/// An [IrcCodec] that produces [Commands]
+pub type CommandsCodec = IrcCodec<Commands<'static>>;
+
+/**
+A codec that decodes lines into `T` and encodes any [Encodable]
+
+`T` can be any [FromIrcMessage] type, such as [IrcMessage] (the default) or [Commands].
+
+With `asynchronous-codec`, its `Encoder` trait allows only one type of item, which is `E` here. With `tokio-util`, any [Encodable] can be encoded.
+
+This uses the same settings as the [Decoder](crate::Decoder):
+* lines longer than [max_line_length](IrcCodec::max_line_length) produce a [DecodeError::LineTooLong], and the rest of the line is skipped.
+* invalid UTF-8 produces a [DecodeError::InvalidUtf8] unless [lossy](IrcCodec::set_lossy) decoding is enabled.
+*/
+pub struct IrcCodec<T = IrcMessage<'static>, E = T> {
+    max_line_length: usize,
+    lossy: bool,
+    mode: ParseMode,
+    // how much of the buffer was already searched for a newline
+    scanned: usize,
+    // whether the rest of a long line is being skipped
+    skipping: bool,
+    _marker: PhantomData<fn(E) -> T>,
+}
+
+impl<T, E> std::fmt::Debug for IrcCodec<T, E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IrcCodec")
+            .field("max_line_length", &self.max_line_length)
+            .field("lossy", &self.lossy)
+            .field("mode", &self.mode)
+            .finish()
+    }
+}
+
+impl<T, E> Default for IrcCodec<T, E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, E> IrcCodec<T, E> {
+    /// Create a new codec
+    pub fn new() -> Self {
+        Self {
+            max_line_length: crate::decoder::DEFAULT_MAX_LINE_LENGTH,
+            lossy: false,
+            mode: ParseMode::Lenient,
+            scanned: 0,
+            skipping: false,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Set how strictly messages are parsed
+    ///
+    /// This defaults to [ParseMode::Lenient]
+    pub fn set_parse_mode(&mut self, mode: ParseMode) {
+        self.mode = mode;
+    }
+
+    /// Get how strictly messages are parsed
+    pub fn parse_mode(&self) -> ParseMode {
+        self.mode
+    }
+
+    /// Set the maximum length of a line, in bytes (including the trailing `\r\n`).
+    ///
+    /// This defaults to [DEFAULT_MAX_LINE_LENGTH](crate::decoder::DEFAULT_MAX_LINE_LENGTH).
+    pub fn set_max_line_length(&mut self, max: usize) {
+        self.max_line_length = max;
+    }
+
+    /// Get the maximum length of a line, in bytes
+    pub fn max_line_length(&self) -> usize {
+        self.max_line_length
+    }
+
+    /// Replace any invalid UTF-8 with `U+FFFD REPLACEMENT CHARACTER` rather than returning an error.
+    ///
+    /// This is disabled by default.
+    pub fn set_lossy(&mut self, lossy: bool) {
+        self.lossy = lossy;
+    }
+
+    /// Whether invalid UTF-8 is replaced rather than returning an error
+    pub fn is_lossy(&self) -> bool {
+        self.lossy
+    }
+
+    fn next_frame(&mut self, src: &[u8]) -> Frame {
+        if src.is_empty() {
+            return Frame::Incomplete;
+        }
+
+        let start = self.scanned.min(src.len());
+        match src[start..].iter().position(|&c| c == b'\n') {
+            Some(pos) => {
+                let end = start + pos + 1;
+                self.scanned = 0;
+                // this is the end of a line that was already reported
+                if std::mem::replace(&mut self.skipping, false) {
+                    return Frame::Discard(end, None);
+                }
+                if end > self.max_line_length {
+                    return Frame::Discard(end, Some(self.too_long()));
+                }
+                Frame::Line(end)
+            }
+            None if self.skipping => Frame::Discard(src.len(), None),
+            None if src.len() > self.max_line_length => {
+                self.skipping = true;
+                self.scanned = 0;
+                Frame::Discard(src.len(), Some(self.too_long()))
+            }
+            None => {
+                self.scanned = src.len();
+                Frame::Incomplete
+            }
+        }
+    }
+
+    fn too_long(&self) -> DecodeError {
+        DecodeError::LineTooLong {
+            max: self.max_line_length,
+        }
+    }
+
+    fn decode_from<B>(&mut self, src: &mut B) -> Result<Option<T>, DecodeError>
+    where
+        B: Buffer,
+        T: FromIrcMessage<'static>,
+        T::Error: Into<DecodeError>,
+    {
+        loop {
+            match self.next_frame(src) {
+                Frame::Line(end) => {
+                    let line = src.split_to(end);
+                    return self.parse(&line).map(Some);
+                }
+                Frame::Discard(end, err) => {
+                    drop(src.split_to(end));
+                    if let Some(err) = err {
+                        return Err(err);
+                    }
+                }
+                Frame::Incomplete => return Ok(None),
+            }
+        }
+    }
+
+    fn parse(&self, line: &[u8]) -> Result<T, DecodeError>
+    where
+        T: FromIrcMessage<'static>,
+        T::Error: Into<DecodeError>,
+    {
+        let line: std::sync::Arc<str> = if self.lossy {
+            String::from_utf8_lossy(line).into()
+        } else {
+            std::str::from_utf8(line)
+                .map_err(DecodeError::InvalidUtf8)?
+                .into()
+        };
+
+        let msg = IrcMessage::parse_with_mode(MaybeOwned::Shared(line), self.mode)?;
+        T::from_irc(msg).map_err(Into::into)
+    }
+}
+
+enum Frame {
+    // a complete line of this length
+    Line(usize),
+    // throw away this many bytes, and then return the error
+    Discard(usize, Option<DecodeError>),
+    // wait for more data
+    Incomplete,
+}
+
+// the two codec crates use different versions of `bytes`
+trait Buffer: Deref<Target = [u8]> + Sized {
+    fn split_to(&mut self, at: usize) -> Self;
+    fn extend_from_slice(&mut self, data: &[u8]);
+}
+
+struct BufferWriter<'a, B>(&'a mut B);
+
+impl<'a, B: Buffer> Write for BufferWriter<'a, B> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+macro_rules! impl_buffer {
+    ($ty:ty) => {
+        impl Buffer for $ty {
+            fn split_to(&mut self, at: usize) -> Self {
+                <$ty>::split_to(self, at)
+            }
+            fn extend_from_slice(&mut self, data: &[u8]) {
+                <$ty>::extend_from_slice(self, data)
+            }
+        }
+    };
+}
+
+#[cfg(feature = "tokio-util")]
+impl_buffer!(bytes::BytesMut);
+
+#[cfg(feature = "asynchronous-codec")]
+impl_buffer!(asynchronous_codec::BytesMut);
+
+#[cfg(feature = "tokio-util")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio-util")))]
+impl<T, E> tokio_util::codec::Decoder for IrcCodec<T, E>
+where
+    T: FromIrcMessage<'static>,
+    T::Error: Into<DecodeError>,
+{
+    type Item = T;
+    type Error = DecodeError;
+
+    fn decode(&mut self, src: &mut bytes::BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        self.decode_from(src)
+    }
+}
+
+#[cfg(feature = "tokio-util")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio-util")))]
+impl<T, E, M> tokio_util::codec::Encoder<M> for IrcCodec<T, E>
+where
+    M: Encodable,
+{
+    type Error = std::io::Error;
+
+    fn encode(&mut self, item: M, dst: &mut bytes::BytesMut) -> Result<(), Self::Error> {
+        item.encode(&mut BufferWriter(dst))
+    }
+}
+
+#[cfg(feature = "asynchronous-codec")]
+#[cfg_attr(docsrs, doc(cfg(feature = "asynchronous-codec")))]
+impl<T, E> asynchronous_codec::Decoder for IrcCodec<T, E>
+where
+    T: FromIrcMessage<'static>,
+    T::Error: Into<DecodeError>,
+{
+    type Item = T;
+    type Error = DecodeError;
+
+    fn decode(
+        &mut self,
+        src: &mut asynchronous_codec::BytesMut,
+    ) -> Result<Option<Self::Item>, Self::Error> {
+        self.decode_from(src)
+    }
+}
+
+#[cfg(feature = "asynchronous-codec")]
+#[cfg_attr(docsrs, doc(cfg(feature = "asynchronous-codec")))]
+impl<T, E> asynchronous_codec::Encoder for IrcCodec<T, E>
+where
+    E: Encodable,
+{
+    type Item = E;
+    type Error = std::io::Error;
+
+    fn encode(
+        &mut self,
+        item: Self::Item,
+        dst: &mut asynchronous_codec::BytesMut,
+    ) -> Result<(), Self::Error> {
+        item.encode(&mut BufferWriter(dst))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the decoding is shared, so this just uses a Vec
+    impl Buffer for Vec<u8> {
+        fn split_to(&mut self, at: usize) -> Self {
+            let tail = self.split_off(at);
+            std::mem::replace(self, tail)
+        }
+        fn extend_from_slice(&mut self, data: &[u8]) {
+            Vec::extend_from_slice(self, data)
+        }
+    }
+
+    fn feed<T, E>(
+        codec: &mut IrcCodec<T, E>,
+        buf: &mut Vec<u8>,
+        data: &[u8],
+    ) -> Vec<Result<T, DecodeError>>
+    where
+        T: FromIrcMessage<'static>,
+        T::Error: Into<DecodeError>,
+    {
+        buf.extend_from_slice(data);
+        let mut out = vec![];
+        loop {
+            match codec.decode_from(buf) {
+                Ok(Some(msg)) => out.push(Ok(msg)),
+                Ok(None) => break,
+                Err(err) => out.push(Err(err)),
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn partial_lines() {
+        let mut codec = CommandsCodec::new();
+        let mut buf = vec![];
+
+        assert!(feed(&mut codec, &mut buf, b"PING :12").is_empty());
+        assert!(feed(&mut codec, &mut buf, b"34\r").is_empty());
+
+        let out = feed(
+            &mut codec,
+            &mut buf,
+            b"\n:museun!museun@museun PRIVMSG #museun :hello\r\nPI",
+        );
+        assert_eq!(out.len(), 2);
+        assert!(matches!(out[0], Ok(Commands::Ping { .. })));
+        assert!(matches!(out[1], Ok(Commands::Privmsg { .. })));
+
+        let out = feed(&mut codec, &mut buf, b"NG :5678\r\n");
+        assert_eq!(out.len(), 1);
+        assert!(matches!(out[0], Ok(Commands::Ping { .. })));
+
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn line_too_long() {
+        let mut codec = IrcCodec::<IrcMessage<'static>>::new();
+        codec.set_max_line_length(16);
+        let mut buf = vec![];
+
+        // a partial line that is already too long
+        let out = feed(&mut codec, &mut buf, b"PRIVMSG #museun :aaaaaaaa");
+        assert!(matches!(
+            out[..],
+            [Err(DecodeError::LineTooLong { max: 16 })]
+        ));
+        assert!(buf.is_empty());
+
+        // the rest of it is skipped
+        assert!(feed(&mut codec, &mut buf, b"aaaaaaaa").is_empty());
+        let out = feed(&mut codec, &mut buf, b"aaaa\r\nPING :1234\r\n");
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].as_ref().unwrap().get_data(), Some("1234"));
+
+        // a complete line that is too long
+        let out = feed(&mut codec, &mut buf, b"PING :12345678901234\r\nPING :1\r\n");
+        assert_eq!(out.len(), 2);
+        assert!(matches!(out[0], Err(DecodeError::LineTooLong { max: 16 })));
+        assert_eq!(out[1].as_ref().unwrap().get_data(), Some("1"));
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let mut codec = IrcCodec::<IrcMessage<'static>>::new();
+        let mut buf = vec![];
+
+        let out = feed(&mut codec, &mut buf, b"PRIVMSG #museun :\xFFhello\r\n");
+        assert!(matches!(out[..], [Err(DecodeError::InvalidUtf8(..))]));
+
+        codec.set_lossy(true);
+        let out = feed(&mut codec, &mut buf, b"PRIVMSG #museun :\xFFhello\r\n");
+        assert_eq!(out[0].as_ref().unwrap().get_data(), Some("\u{FFFD}hello"));
+    }
+
+    #[test]
+    fn encode() {
+        let mut buf = vec![];
+        crate::commands::privmsg("#museun", "hello")
+            .encode(&mut BufferWriter(&mut buf))
+            .unwrap();
+        assert_eq!(buf, b"PRIVMSG #museun :hello\r\n");
+    }
+}
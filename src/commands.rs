@@ -101,6 +101,7 @@ export_commands! {
     vip             => Vip
     vips            => Vips
     whisper         => Whisper
+    with_tags       => WithTags
 }
 
 macro_rules! serde_for_commands {
@@ -121,11 +122,13 @@ macro_rules! serde_for_commands {
                     let mut data = vec![];
                     self.encode(&mut data).map_err(Error::custom)?;
                     let raw = std::str::from_utf8(&data).map_err(Error::custom)?;
+                    // don't leak the token from a `Register`
+                    let raw = $crate::trovo::secret::mask_pass(raw);
 
                     let len = serde_for_commands!(@len $($field),*);
 
                     let mut s = serializer.serialize_struct(stringify!($ty), std::cmp::max(len, 1))?;
-                    s.serialize_field("raw", raw)?;
+                    s.serialize_field("raw", &*raw)?;
                     $( s.serialize_field(stringify!($field), &self.$field)?; )*
                     s.end()
                 }
@@ -32,15 +32,30 @@ impl<'a> Encodable for Register<'a> {
             name,
             token,
             capabilities,
+            optional_capabilities,
+            custom_capabilities,
+            optional_custom_capabilities,
+            list_capabilities,
         } = &self.user_config;
 
+        if *list_capabilities {
+            write!(buf, "CAP LS 302\r\n")?;
+        }
+
         // the caps have to be written first
-        for cap in capabilities {
+        for cap in capabilities.iter().chain(optional_capabilities) {
             let cap = cap.encode_as_str();
             write!(buf, "{}\r\n", cap)?;
         }
 
-        write!(buf, "PASS {}\r\n", token)?;
+        for name in custom_capabilities
+            .iter()
+            .chain(optional_custom_capabilities)
+        {
+            write!(buf, "CAP REQ :{}\r\n", name)?;
+        }
+
+        write!(buf, "PASS {}\r\n", token.expose())?;
         write!(buf, "NICK {}\r\n", name)?;
         buf.flush()
     }
@@ -50,6 +65,7 @@ impl<'a> Encodable for Register<'a> {
 mod tests {
     use super::super::*;
     use super::*;
+    use crate::trovo::Capability;
 
     #[test]
     fn register_encode() {
@@ -70,21 +86,68 @@ mod tests {
     }
 
     #[test]
-    #[cfg(feature = "serde")]
-    fn register_serde() {
+    fn register_encode_optional_caps() {
         let config = UserConfig::builder()
             .anonymous()
-            .enable_all_capabilities()
+            .capabilities(&[Capability::Tags])
+            .optional_custom_capabilities(&["example.com/foo"])
+            .list_capabilities()
             .build()
             .unwrap();
 
-        test_serde(
+        test_encode(
             register(&config),
-            "CAP REQ :trovo.tv/membership\r\n\
+            "CAP LS 302\r\n\
             CAP REQ :trovo.tv/tags\r\n\
-            CAP REQ :trovo.tv/commands\r\n\
+            CAP REQ :example.com/foo\r\n\
             PASS justinfan1234\r\n\
             NICK justinfan1234\r\n",
         )
     }
+
+    #[test]
+    fn register_debug_redacted() {
+        let token = format!("oauth:{}", "a".repeat(30));
+        let config = UserConfig::builder()
+            .name("museun")
+            .token(&token)
+            .build()
+            .unwrap();
+
+        let debug = format!("{:?}", register(&config));
+        assert!(!debug.contains(&token));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn register_serde() {
+        let token = format!("oauth:{}", "a".repeat(30));
+        let config = UserConfig::builder()
+            .name("museun")
+            .token(&token)
+            .enable_all_capabilities()
+            .build()
+            .unwrap();
+
+        let json = serde_json::to_string_pretty(&register(&config)).unwrap();
+        assert!(!json.contains(&token));
+
+        #[derive(Debug, PartialEq, ::serde::Deserialize)]
+        struct Wrapper {
+            raw: String,
+        }
+
+        let wrapper: Wrapper = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            wrapper.raw,
+            "CAP REQ :trovo.tv/membership\r\n\
+            CAP REQ :trovo.tv/tags\r\n\
+            CAP REQ :trovo.tv/commands\r\n\
+            PASS <redacted>\r\n\
+            NICK museun\r\n",
+        );
+
+        // a redacted token cannot be deserialized
+        assert!(serde_json::from_str::<Register>(&json).is_err());
+    }
 }
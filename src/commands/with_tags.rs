@@ -0,0 +1,164 @@
+use super::Encodable;
+use crate::irc::TagMap;
+use std::io::{Result, Write};
+
+/// Prefix any command with these tags
+///
+/// If the command already has tags, these are merged into them (replacing any with the same key)
+///
+/// If the command writes more than one line, the tags are applied to each line
+#[non_exhaustive]
+#[must_use = "commands must be encoded"]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct WithTags<'a, T> {
+    pub(crate) tags: &'a TagMap,
+    pub(crate) command: T,
+}
+
+/**
+Prefix any command with these tags
+
+This can be used to send client-only (`+`) tags with a message.
+
+```
+# use trovochat::{commands, irc::TagMap, Encodable as _};
+let mut tags = TagMap::new();
+tags.insert("+example.com/foo", "hello world");
+
+let mut out = vec![];
+commands::with_tags(&tags, commands::privmsg("#museun", "hi"))
+    .encode(&mut out)
+    .unwrap();
+
+assert_eq!(
+    std::str::from_utf8(&out).unwrap(),
+    "@+example.com/foo=hello\\sworld PRIVMSG #museun :hi\r\n"
+);
+```
+*/
+pub const fn with_tags<T>(tags: &TagMap, command: T) -> WithTags<'_, T> {
+    WithTags { tags, command }
+}
+
+impl<'a, T> Encodable for WithTags<'a, T>
+where
+    T: Encodable,
+{
+    fn encode<W>(&self, buf: &mut W) -> Result<()>
+    where
+        W: Write + ?Sized,
+    {
+        if self.tags.is_empty() {
+            return self.command.encode(buf);
+        }
+
+        let mut data = vec![];
+        self.command.encode(&mut data)?;
+
+        let mut rest = &data[..];
+        while !rest.is_empty() {
+            let end = rest
+                .windows(2)
+                .position(|w| w == b"\r\n")
+                .map_or(rest.len(), |pos| pos + 2);
+            let (line, tail) = rest.split_at(end);
+            self.encode_line(line, buf)?;
+            rest = tail;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, T> WithTags<'a, T> {
+    fn encode_line<W>(&self, line: &[u8], buf: &mut W) -> Result<()>
+    where
+        W: Write + ?Sized,
+    {
+        let (mut tags, tail) = match line.iter().position(|&c| c == b' ') {
+            Some(pos) if line.starts_with(b"@") => {
+                let existing = std::str::from_utf8(&line[..pos])
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+                (TagMap::parse(existing), &line[pos + 1..])
+            }
+            _ => (TagMap::new(), line),
+        };
+
+        tags.merge(self.tags.clone());
+        tags.encode(buf)?;
+        buf.write_all(tail)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use super::*;
+
+    const TEST_UUID: &str = "aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa";
+
+    #[test]
+    fn with_tags_encode() {
+        let mut tags = TagMap::new();
+        tags.insert("+example.com/foo", "a;b");
+        tags.insert("+client", "");
+
+        test_encode(
+            with_tags(&tags, privmsg("#museun", "this is a test of a line")),
+            "@+example.com/foo=a\\:b;+client= PRIVMSG #museun :this is a test of a line\r\n",
+        );
+
+        test_encode(
+            with_tags(
+                &TagMap::new(),
+                privmsg("#museun", "this is a test of a line"),
+            ),
+            "PRIVMSG #museun :this is a test of a line\r\n",
+        );
+    }
+
+    #[test]
+    fn with_tags_merge_encode() {
+        let mut tags = TagMap::new();
+        tags.insert("+example.com/foo", "bar");
+
+        test_encode(
+            with_tags(&tags, reply("#museun", TEST_UUID, "this is a test of a line")),
+            format!(
+                "@reply-parent-msg-id={};+example.com/foo=bar PRIVMSG #museun :this is a test of a line\r\n",
+                TEST_UUID
+            ),
+        );
+
+        tags.insert("reply-parent-msg-id", "something-else");
+        test_encode(
+            with_tags(&tags, reply("#museun", TEST_UUID, "this is a test of a line")),
+            "@reply-parent-msg-id=something-else;+example.com/foo=bar PRIVMSG #museun :this is a test of a line\r\n",
+        );
+    }
+
+    #[test]
+    fn with_tags_multiple_lines() {
+        let mut tags = TagMap::new();
+        tags.insert("+client", "");
+
+        let config = crate::UserConfig::builder()
+            .anonymous()
+            .capabilities(&[crate::trovo::Capability::Tags])
+            .build()
+            .unwrap();
+
+        test_encode(
+            with_tags(&tags, register(&config)),
+            "@+client= CAP REQ :trovo.tv/tags\r\n\
+            @+client= PASS justinfan1234\r\n\
+            @+client= NICK justinfan1234\r\n",
+        );
+    }
+
+    #[test]
+    fn with_tags_invalid_key() {
+        let mut tags = TagMap::new();
+        tags.insert("bad key", "");
+        assert!(with_tags(&tags, ping("1234")).encode(&mut vec![]).is_err());
+    }
+}
@@ -1,5 +1,5 @@
 cfg_async! {
-use crate::{irc::IrcMessage, IntoOwned,DecodeError};
+use crate::{irc::{IrcMessage, IrcMessageBytes, ParseMode}, messages::Commands, DecodeError, FromIrcMessage, IntoOwned};
 
 use std::{
     future::Future,
@@ -17,6 +17,10 @@ use futures_lite::{io::BufReader as AsyncBufReader, AsyncBufReadExt, AsyncRead,
 pub struct AsyncDecoder<R> {
     reader: AsyncBufReader<R>,
     buf: Vec<u8>,
+    lossy: bool,
+    max_line_length: usize,
+    mode: ParseMode,
+    pending: Option<DecodeError>,
 }
 
 impl<R> std::fmt::Debug for AsyncDecoder<R> {
@@ -30,35 +34,190 @@ impl<R: AsyncRead + Send + Sync + Unpin> AsyncDecoder<R> {
     pub fn new(reader: R) -> Self {
         Self {
             reader: AsyncBufReader::new(reader),
-            buf: Vec::with_capacity(1024),
+            buf: Vec::with_capacity(super::INITIAL_CAPACITY),
+            lossy: false,
+            max_line_length: super::DEFAULT_MAX_LINE_LENGTH,
+            mode: ParseMode::Lenient,
+            pending: None,
         }
     }
 
+    /// Set how strictly messages are parsed
+    ///
+    /// This defaults to [ParseMode::Lenient]
+    pub fn set_parse_mode(&mut self, mode: ParseMode) {
+        self.mode = mode;
+    }
+
+    /// Get how strictly messages are parsed
+    pub fn parse_mode(&self) -> ParseMode {
+        self.mode
+    }
+
+    /// Set the maximum length of a line, in bytes (including the trailing `\r\n`).
+    ///
+    /// A longer line produces a [DecodeError::LineTooLong], and the rest of it is skipped.
+    ///
+    /// This defaults to [DEFAULT_MAX_LINE_LENGTH](super::DEFAULT_MAX_LINE_LENGTH).
+    pub fn set_max_line_length(&mut self, max: usize) {
+        self.max_line_length = max;
+    }
+
+    /// Get the maximum length of a line, in bytes
+    pub fn max_line_length(&self) -> usize {
+        self.max_line_length
+    }
+
+    /// Replace any invalid UTF-8 with `U+FFFD REPLACEMENT CHARACTER` rather than returning an error.
+    ///
+    /// This is disabled by default.
+    pub fn set_lossy(&mut self, lossy: bool) {
+        self.lossy = lossy;
+    }
+
+    /// Whether invalid UTF-8 is replaced rather than returning an error
+    pub fn is_lossy(&self) -> bool {
+        self.lossy
+    }
+
+    /// Get the raw bytes of the last line read.
+    ///
+    /// This is useful for reporting a line that could not be decoded.
+    pub fn last_line(&self) -> &[u8] {
+        &self.buf
+    }
+
     /// Read the next message.
     ///
     /// This returns a borrowed [IrcMessage] which is valid until the next AsyncDecoder call is made.
     ///
     /// If you just want an owned one, use the [AsyncDecoder] as an stream. e.g. dec.next().
     pub async fn read_message(&mut self) -> Result<IrcMessage<'_>, DecodeError> {
-        self.buf.clear();
-        let n = self
-            .reader
-            .read_until(b'\n', &mut self.buf)
-            .await
-            .map_err(DecodeError::Io)?;
-        if n == 0 {
-            return Err(DecodeError::Eof);
+        self.read_message_bytes()
+            .await?
+            .to_irc_message()
+            .map_err(DecodeError::InvalidUtf8)
+    }
+
+    /// Read every complete message that is already buffered, appending them to `out`.
+    ///
+    /// This waits for at least one message, and then parses any other complete lines without reading from the underlying reader again.
+    ///
+    /// This returns how many messages were appended.
+    ///
+    /// If a line after the first one could not be decoded, the messages before it are returned and the error is returned by the next read.
+    pub async fn read_batch(&mut self, out: &mut Vec<Commands<'static>>) -> Result<usize, DecodeError> {
+        self.read_batch_with(out, |msg| Some(msg.into_owned())).await
+    }
+
+    /// Read every complete message that is already buffered, handing each borrowed message to `func` and appending what it returns to `out`.
+    ///
+    /// Nothing is allocated for a message unless `func` does so, e.g. by calling [IntoOwned::into_owned] on it. Returning `None` skips the message.
+    ///
+    /// This otherwise works like [read_batch](Self::read_batch), and returns how many messages were appended.
+    pub async fn read_batch_with<T, F>(&mut self, out: &mut Vec<T>, mut func: F) -> Result<usize, DecodeError>
+    where
+        F: FnMut(Commands<'_>) -> Option<T>,
+    {
+        let start = out.len();
+        let mut read = 0;
+        loop {
+            let msg = self
+                .read_message().await
+                .and_then(|msg| Commands::from_irc(msg).map_err(DecodeError::ParseError));
+
+            match msg {
+                Ok(msg) => {
+                    read += 1;
+                    out.extend(func(msg));
+                }
+                Err(err) if read == 0 => return Err(err),
+                Err(err) => {
+                    self.pending.replace(err);
+                    break;
+                }
+            }
+
+            if !self.reader.buffer().contains(&b'\n') {
+                break;
+            }
         }
+        Ok(out.len() - start)
+    }
+
+    /// Read the next message, without validating all of it as UTF-8.
+    ///
+    /// This returns a borrowed [IrcMessageBytes] which is valid until the next AsyncDecoder call is made.
+    pub async fn read_message_bytes(&mut self) -> Result<IrcMessageBytes<'_>, DecodeError> {
+        if let Some(err) = self.pending.take() {
+            return Err(err);
+        }
+
+        self.read_line().await?;
 
-        let str = std::str::from_utf8(&self.buf[..n]).map_err(DecodeError::InvalidUtf8)?;
-        log::trace!("< {}", str.escape_debug());
+        log::trace!(
+            "< {}",
+            crate::trovo::secret::mask_pass(&String::from_utf8_lossy(&self.buf)).escape_debug()
+        );
+
+        if self.lossy {
+            super::replace_invalid_utf8(&mut self.buf);
+        }
 
         // this should only ever parse 1 message
-        crate::irc::parse_one(str)
+        crate::irc::parse_one_bytes_with_mode(&self.buf, self.mode)
             .map_err(DecodeError::ParseError)
             .map(|(_, msg)| msg)
     }
 
+    async fn read_line(&mut self) -> Result<(), DecodeError> {
+        // the last line was small, so give back the memory from an earlier spike
+        if self.buf.capacity() > super::RETAINED_CAPACITY && self.buf.len() < super::RETAINED_CAPACITY {
+            self.buf = Vec::with_capacity(super::INITIAL_CAPACITY);
+        }
+        self.buf.clear();
+
+        let mut too_long = false;
+        loop {
+            let available = match self.reader.fill_buf().await {
+                Ok(available) => available,
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(DecodeError::Io(err)),
+            };
+
+            if available.is_empty() {
+                break;
+            }
+
+            let (done, used) = match available.iter().position(|&c| c == b'\n') {
+                Some(pos) => (true, pos + 1),
+                None => (false, available.len()),
+            };
+
+            // keep what fits, and skip the rest of the line
+            let room = self.max_line_length.saturating_sub(self.buf.len());
+            too_long |= used > room;
+            self.buf.extend_from_slice(&available[..used.min(room)]);
+            self.reader.consume(used);
+
+            if done {
+                break;
+            }
+        }
+
+        if too_long {
+            return Err(DecodeError::LineTooLong {
+                max: self.max_line_length,
+            });
+        }
+
+        if self.buf.is_empty() {
+            return Err(DecodeError::Eof);
+        }
+
+        Ok(())
+    }
+
     /// Consume the decoder returning the inner Reader
     pub fn into_inner(self) -> R {
         self.reader.into_inner()
@@ -121,5 +280,43 @@ mod tests {
 
         futures_lite::future::block_on(fut);
     }
+
+    #[test]
+    fn read_async_batch() {
+        let fut = async move {
+            let data = b"PING :1234\r\nPING :5678\r\nPRIVMSG #museun :".to_vec();
+            let mut dec = AsyncDecoder::new(futures_lite::io::Cursor::new(data));
+
+            // the partial line isn't part of the batch
+            let mut out = vec![];
+            assert_eq!(dec.read_batch(&mut out).await.unwrap(), 2);
+            assert!(out.iter().all(|msg| matches!(msg, Commands::Ping { .. })));
+        };
+
+        futures_lite::future::block_on(fut);
+    }
+
+    #[test]
+    fn read_async_line_too_long() {
+        let fut = async move {
+            let data = format!(
+                "PRIVMSG #museun :{}\r\nPRIVMSG #museun :hello\r\n",
+                "a".repeat(100)
+            );
+            let mut dec = AsyncDecoder::new(futures_lite::io::Cursor::new(data.into_bytes()));
+            dec.set_max_line_length(64);
+
+            assert!(matches!(
+                dec.read_message().await.unwrap_err(),
+                DecodeError::LineTooLong { max: 64 }
+            ));
+
+            // the rest of the long line was skipped
+            let msg = dec.read_message().await.unwrap();
+            assert_eq!(msg.get_data().unwrap(), "hello");
+        };
+
+        futures_lite::future::block_on(fut);
+    }
 }
 }
@@ -29,6 +29,11 @@
 //! let owned: trovochat::IrcMessage<'static> = msg.into_owned();
 //! ```
 //!
+//! # Invalid UTF-8
+//! By default, a line containing invalid UTF-8 produces a [DecodeError::InvalidUtf8].
+//!
+//! Using [Decoder::set_lossy] (or [AsyncDecoder::set_lossy]) replaces any invalid sequences with `U+FFFD REPLACEMENT CHARACTER` instead.
+//!
 //! # Owned messages
 //! ```
 //! let input = "@key1=val;key2=true :user!user@user PRIVMSG #some_channel :\x01ACTION hello world\x01\r\n";
@@ -52,3 +57,21 @@ cfg_async! {
 
 mod sync;
 pub use sync::*;
+
+/// The default maximum length of a line, in bytes.
+///
+/// Trovo limits a message to 512 bytes, but the tags can make a line much longer than that.
+pub const DEFAULT_MAX_LINE_LENGTH: usize = 64 * 1024;
+
+// the initial capacity of the line buffer
+const INITIAL_CAPACITY: usize = 1024;
+
+// the line buffer is shrunk back down if it grows past this and then a smaller line is read
+const RETAINED_CAPACITY: usize = 16 * 1024;
+
+/// Replaces any invalid UTF-8 sequences with `U+FFFD REPLACEMENT CHARACTER`
+fn replace_invalid_utf8(buf: &mut Vec<u8>) {
+    if let std::borrow::Cow::Owned(s) = String::from_utf8_lossy(buf) {
+        *buf = s.into_bytes();
+    }
+}
@@ -1,4 +1,8 @@
-use crate::{IntoOwned as _, IrcMessage, MessageError};
+use crate::{
+    irc::{IrcMessageBytes, ParseMode},
+    messages::Commands,
+    FromIrcMessage, IntoOwned, IrcMessage, MessageError,
+};
 use std::io::{BufRead, BufReader, Read};
 
 /// An error produced by a Decoder.
@@ -13,6 +17,11 @@ pub enum DecodeError {
     ParseError(MessageError),
     /// EOF was reached
     Eof,
+    /// A line was longer than the maximum allowed length
+    LineTooLong {
+        /// The maximum length, in bytes
+        max: usize,
+    },
 }
 
 impl std::fmt::Display for DecodeError {
@@ -22,6 +31,7 @@ impl std::fmt::Display for DecodeError {
             Self::InvalidUtf8(err) => write!(f, "invalid utf8: {}", err),
             Self::ParseError(err) => write!(f, "parse error: {}", err),
             Self::Eof => f.write_str("end of file reached"),
+            Self::LineTooLong { max } => write!(f, "line was longer than {} bytes", max),
         }
     }
 }
@@ -37,6 +47,24 @@ impl std::error::Error for DecodeError {
     }
 }
 
+impl From<std::io::Error> for DecodeError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<MessageError> for DecodeError {
+    fn from(err: MessageError) -> Self {
+        Self::ParseError(err)
+    }
+}
+
+impl From<std::convert::Infallible> for DecodeError {
+    fn from(err: std::convert::Infallible) -> Self {
+        match err {}
+    }
+}
+
 /// A decoder over [std::io::Read] that produces [IrcMessage]s
 ///
 /// This will return an [DecodeError::Eof] when reading manually.
@@ -45,6 +73,10 @@ impl std::error::Error for DecodeError {
 pub struct Decoder<R> {
     reader: BufReader<R>,
     buf: Vec<u8>,
+    lossy: bool,
+    max_line_length: usize,
+    mode: ParseMode,
+    pending: Option<DecodeError>,
 }
 
 impl<R> std::fmt::Debug for Decoder<R> {
@@ -61,33 +93,190 @@ where
     pub fn new(reader: R) -> Self {
         Self {
             reader: BufReader::new(reader),
-            buf: Vec::with_capacity(1024),
+            buf: Vec::with_capacity(super::INITIAL_CAPACITY),
+            lossy: false,
+            max_line_length: super::DEFAULT_MAX_LINE_LENGTH,
+            mode: ParseMode::Lenient,
+            pending: None,
         }
     }
 
+    /// Set how strictly messages are parsed
+    ///
+    /// This defaults to [ParseMode::Lenient]
+    pub fn set_parse_mode(&mut self, mode: ParseMode) {
+        self.mode = mode;
+    }
+
+    /// Get how strictly messages are parsed
+    pub fn parse_mode(&self) -> ParseMode {
+        self.mode
+    }
+
+    /// Set the maximum length of a line, in bytes (including the trailing `\r\n`).
+    ///
+    /// A longer line produces a [DecodeError::LineTooLong], and the rest of it is skipped.
+    ///
+    /// This defaults to [DEFAULT_MAX_LINE_LENGTH](super::DEFAULT_MAX_LINE_LENGTH).
+    pub fn set_max_line_length(&mut self, max: usize) {
+        self.max_line_length = max;
+    }
+
+    /// Get the maximum length of a line, in bytes
+    pub fn max_line_length(&self) -> usize {
+        self.max_line_length
+    }
+
+    /// Replace any invalid UTF-8 with `U+FFFD REPLACEMENT CHARACTER` rather than returning an error.
+    ///
+    /// This is disabled by default.
+    pub fn set_lossy(&mut self, lossy: bool) {
+        self.lossy = lossy;
+    }
+
+    /// Whether invalid UTF-8 is replaced rather than returning an error
+    pub fn is_lossy(&self) -> bool {
+        self.lossy
+    }
+
+    /// Get the raw bytes of the last line read.
+    ///
+    /// This is useful for reporting a line that could not be decoded.
+    pub fn last_line(&self) -> &[u8] {
+        &self.buf
+    }
+
     /// Read the next message.
     ///
     /// This returns a borrowed [IrcMessage] which is valid until the next Decoder call is made.
     ///
     /// If you just want an owned one, use the [Decoder] as an iterator. e.g. dec.next().
     pub fn read_message(&mut self) -> Result<IrcMessage<'_>, DecodeError> {
-        self.buf.clear();
-        let n = self
-            .reader
-            .read_until(b'\n', &mut self.buf)
-            .map_err(DecodeError::Io)?;
-        if n == 0 {
-            return Err(DecodeError::Eof);
+        self.read_message_bytes()?
+            .to_irc_message()
+            .map_err(DecodeError::InvalidUtf8)
+    }
+
+    /// Read every complete message that is already buffered, appending them to `out`.
+    ///
+    /// This waits for at least one message, and then parses any other complete lines without reading from the underlying reader again.
+    ///
+    /// This returns how many messages were appended.
+    ///
+    /// If a line after the first one could not be decoded, the messages before it are returned and the error is returned by the next read.
+    pub fn read_batch(&mut self, out: &mut Vec<Commands<'static>>) -> Result<usize, DecodeError> {
+        self.read_batch_with(out, |msg| Some(msg.into_owned()))
+    }
+
+    /// Read every complete message that is already buffered, handing each borrowed message to `func` and appending what it returns to `out`.
+    ///
+    /// Nothing is allocated for a message unless `func` does so, e.g. by calling [IntoOwned::into_owned] on it. Returning `None` skips the message.
+    ///
+    /// This otherwise works like [read_batch](Self::read_batch), and returns how many messages were appended.
+    pub fn read_batch_with<T, F>(
+        &mut self,
+        out: &mut Vec<T>,
+        mut func: F,
+    ) -> Result<usize, DecodeError>
+    where
+        F: FnMut(Commands<'_>) -> Option<T>,
+    {
+        let start = out.len();
+        let mut read = 0;
+        loop {
+            let msg = self
+                .read_message()
+                .and_then(|msg| Commands::from_irc(msg).map_err(DecodeError::ParseError));
+
+            match msg {
+                Ok(msg) => {
+                    read += 1;
+                    out.extend(func(msg));
+                }
+                Err(err) if read == 0 => return Err(err),
+                Err(err) => {
+                    self.pending.replace(err);
+                    break;
+                }
+            }
+
+            if !self.reader.buffer().contains(&b'\n') {
+                break;
+            }
+        }
+        Ok(out.len() - start)
+    }
+
+    /// Read the next message, without validating all of it as UTF-8.
+    ///
+    /// This returns a borrowed [IrcMessageBytes] which is valid until the next Decoder call is made.
+    pub fn read_message_bytes(&mut self) -> Result<IrcMessageBytes<'_>, DecodeError> {
+        if let Some(err) = self.pending.take() {
+            return Err(err);
         }
 
-        let str = std::str::from_utf8(&self.buf[..n]).map_err(DecodeError::InvalidUtf8)?;
+        self.read_line()?;
+
+        if self.lossy {
+            super::replace_invalid_utf8(&mut self.buf);
+        }
 
         // this should only ever parse 1 message
-        crate::irc::parse_one(str)
+        crate::irc::parse_one_bytes_with_mode(&self.buf, self.mode)
             .map_err(DecodeError::ParseError)
             .map(|(_, msg)| msg)
     }
 
+    fn read_line(&mut self) -> Result<(), DecodeError> {
+        // the last line was small, so give back the memory from an earlier spike
+        if self.buf.capacity() > super::RETAINED_CAPACITY
+            && self.buf.len() < super::RETAINED_CAPACITY
+        {
+            self.buf = Vec::with_capacity(super::INITIAL_CAPACITY);
+        }
+        self.buf.clear();
+
+        let mut too_long = false;
+        loop {
+            let available = match self.reader.fill_buf() {
+                Ok(available) => available,
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(DecodeError::Io(err)),
+            };
+
+            if available.is_empty() {
+                break;
+            }
+
+            let (done, used) = match available.iter().position(|&c| c == b'\n') {
+                Some(pos) => (true, pos + 1),
+                None => (false, available.len()),
+            };
+
+            // keep what fits, and skip the rest of the line
+            let room = self.max_line_length.saturating_sub(self.buf.len());
+            too_long |= used > room;
+            self.buf.extend_from_slice(&available[..used.min(room)]);
+            self.reader.consume(used);
+
+            if done {
+                break;
+            }
+        }
+
+        if too_long {
+            return Err(DecodeError::LineTooLong {
+                max: self.max_line_length,
+            });
+        }
+
+        if self.buf.is_empty() {
+            return Err(DecodeError::Eof);
+        }
+
+        Ok(())
+    }
+
     /// Returns an iterator over messages.
     ///
     /// This will produce Results of Messages until an EOF is received
@@ -139,4 +328,134 @@ mod tests {
         }
         assert!(matches!(dec.read_message().unwrap_err(), DecodeError::Eof))
     }
+
+    #[test]
+    fn read_sync_batch() {
+        let data =
+            b":test!test@test PRIVMSG #museun :hello\r\nPING :1234\r\nRECONNECT\r\n".to_vec();
+        let mut dec = Decoder::new(std::io::Cursor::new(data));
+
+        let mut out = vec![];
+        assert_eq!(dec.read_batch(&mut out).unwrap(), 3);
+        assert!(matches!(out[0], Commands::Privmsg { .. }));
+        assert!(matches!(out[1], Commands::Ping { .. }));
+        assert!(matches!(out[2], Commands::Reconnect { .. }));
+
+        assert!(matches!(
+            dec.read_batch(&mut out).unwrap_err(),
+            DecodeError::Eof
+        ));
+        assert_eq!(out.len(), 3);
+    }
+
+    #[test]
+    fn read_sync_batch_with() {
+        let data =
+            b":test!test@test PRIVMSG #museun :hello\r\nPING :1234\r\n:test!test@test PRIVMSG #museun :world\r\n".to_vec();
+        let mut dec = Decoder::new(std::io::Cursor::new(data));
+
+        let mut seen = 0;
+        let mut out = vec![];
+        let n = dec
+            .read_batch_with(&mut out, |msg| {
+                seen += 1;
+                match msg {
+                    Commands::Privmsg(msg) => Some(msg.data().to_string()),
+                    _ => None,
+                }
+            })
+            .unwrap();
+
+        assert_eq!(n, 2);
+        assert_eq!(seen, 3);
+        assert_eq!(out, vec!["hello", "world"]);
+
+        assert!(matches!(
+            dec.read_batch_with(&mut out, |_| Some(String::new()))
+                .unwrap_err(),
+            DecodeError::Eof
+        ));
+    }
+
+    #[test]
+    fn read_sync_batch_bad_line() {
+        let data = b"PING :1234\r\nPRIVMSG #museun :\xFF\r\nPING :5678\r\n".to_vec();
+        let mut dec = Decoder::new(std::io::Cursor::new(data));
+
+        // the good line is returned first
+        let mut out = vec![];
+        assert_eq!(dec.read_batch(&mut out).unwrap(), 1);
+
+        // and then the error for the bad line
+        assert!(matches!(
+            dec.read_batch(&mut out).unwrap_err(),
+            DecodeError::InvalidUtf8(..)
+        ));
+        assert_eq!(dec.last_line(), b"PRIVMSG #museun :\xFF\r\n");
+
+        assert_eq!(dec.read_batch(&mut out).unwrap(), 1);
+        assert_eq!(out.len(), 2);
+    }
+
+    #[test]
+    fn read_sync_bytes() {
+        let data = b"PRIVMSG #museun :\xFF\r\nPRIVMSG #museun :\xFF\r\n".to_vec();
+        let mut dec = Decoder::new(std::io::Cursor::new(data));
+
+        let msg = dec.read_message_bytes().unwrap();
+        assert_eq!(msg.get_command().unwrap(), "PRIVMSG");
+        assert_eq!(msg.nth_arg(0).unwrap().unwrap(), "#museun");
+
+        assert!(matches!(
+            dec.read_message().unwrap_err(),
+            DecodeError::InvalidUtf8(..)
+        ));
+    }
+
+    #[test]
+    fn read_sync_lossy() {
+        let data = b"PRIVMSG #museun :\xFFhello\r\n".to_vec();
+        let mut dec = Decoder::new(std::io::Cursor::new(data));
+        dec.set_lossy(true);
+
+        let msg = dec.read_message().unwrap();
+        assert_eq!(msg.get_data().unwrap(), "\u{FFFD}hello");
+    }
+
+    #[test]
+    fn read_sync_line_too_long() {
+        let data = format!(
+            "PRIVMSG #museun :{}\r\nPRIVMSG #museun :hello\r\n",
+            "a".repeat(100)
+        );
+        let mut dec = Decoder::new(std::io::Cursor::new(data.into_bytes()));
+        dec.set_max_line_length(64);
+
+        assert!(matches!(
+            dec.read_message().unwrap_err(),
+            DecodeError::LineTooLong { max: 64 }
+        ));
+        assert_eq!(dec.last_line().len(), 64);
+
+        // the rest of the long line was skipped
+        let msg = dec.read_message().unwrap();
+        assert_eq!(msg.get_data().unwrap(), "hello");
+        assert!(matches!(dec.read_message().unwrap_err(), DecodeError::Eof));
+    }
+
+    #[test]
+    fn read_sync_shrink_buffer() {
+        let data = format!(
+            "PRIVMSG #museun :{}\r\nPRIVMSG #museun :a\r\nPRIVMSG #museun :b\r\n",
+            "a".repeat(super::super::RETAINED_CAPACITY * 2)
+        );
+        let mut dec = Decoder::new(std::io::Cursor::new(data.into_bytes()));
+
+        dec.read_message().unwrap();
+        assert!(dec.buf.capacity() > super::super::RETAINED_CAPACITY);
+
+        dec.read_message().unwrap();
+        dec.read_message().unwrap();
+        assert!(dec.buf.capacity() <= super::super::RETAINED_CAPACITY);
+    }
 }
@@ -0,0 +1,170 @@
+//! Support for the code generated by `trovochat-derive`
+//!
+//! This isn't part of the public API.
+use crate::{
+    irc::{TagIndices, Tags},
+    MaybeOwned, MessageError,
+};
+use std::str::FromStr;
+
+#[cfg(feature = "serde")]
+pub use crate::serde::RawVisitor;
+#[cfg(feature = "serde")]
+pub use ::serde;
+
+/// Parse a tag, returning an error if it is missing
+pub fn required_tag<T>(
+    raw: &MaybeOwned<'_>,
+    indices: &TagIndices,
+    name: &str,
+) -> Result<T, MessageError>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    optional_tag(raw, indices, name)?.ok_or_else(|| MessageError::ExpectedTag {
+        name: name.to_string(),
+    })
+}
+
+/// Parse a tag, if it exists
+pub fn optional_tag<T>(
+    raw: &MaybeOwned<'_>,
+    indices: &TagIndices,
+    name: &str,
+) -> Result<Option<T>, MessageError>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    Tags::from_data_indices(raw, indices)
+        .get_unescaped(name)
+        .as_deref()
+        .map(T::from_str)
+        .transpose()
+        .map_err(|err| MessageError::CannotParseTag {
+            name: name.to_string(),
+            error: Box::new(err),
+        })
+}
+
+/// Keep the generated serde impls only if the `serde` feature is enabled
+#[cfg(feature = "serde")]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __cfg_serde {
+    ($($tt:tt)*) => { $($tt)* };
+}
+
+/// Keep the generated serde impls only if the `serde` feature is enabled
+#[cfg(not(feature = "serde"))]
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __cfg_serde {
+    ($($tt:tt)*) => {};
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        irc::TagIndices,
+        maybe_owned::{MaybeOwned, MaybeOwnedIndex},
+        messages::{Commands, CustomMessage, Registry},
+        FromIrcMessage, IntoOwned as _, MessageError,
+    };
+
+    #[derive(Debug, Clone, PartialEq, FromIrcMessage)]
+    #[irc(command = "HOSTED")]
+    struct Hosted<'a> {
+        raw: MaybeOwned<'a>,
+        tags: TagIndices,
+        #[irc(nick)]
+        name: Option<MaybeOwnedIndex>,
+        #[irc(arg = 0)]
+        channel: MaybeOwnedIndex,
+        #[irc(arg = 1)]
+        target: Option<MaybeOwnedIndex>,
+        #[irc(data)]
+        data: MaybeOwnedIndex,
+        #[irc(tag = "viewers")]
+        viewers: Option<u64>,
+    }
+
+    impl CustomMessage for Hosted<'static> {
+        fn raw(&self) -> &str {
+            self.raw()
+        }
+    }
+
+    fn parse(input: &str) -> Result<Hosted<'_>, MessageError> {
+        Hosted::from_irc(crate::irc::parse_one(input).unwrap().1)
+    }
+
+    #[test]
+    fn derive() {
+        let msg = parse(
+            "@viewers=10 :museun!museun@museun.tmi.trovo.tv HOSTED #museun #shaken_bot :hello\r\n",
+        )
+        .unwrap();
+        assert_eq!(msg.name(), Some("museun"));
+        assert_eq!(msg.channel(), "#museun");
+        assert_eq!(msg.target(), Some("#shaken_bot"));
+        assert_eq!(msg.data(), "hello");
+        assert_eq!(msg.viewers(), Some(10));
+        assert_eq!(msg.tags().get("viewers"), Some("10"));
+
+        let msg = parse(":tmi.trovo.tv HOSTED #museun :hello\r\n").unwrap();
+        assert_eq!(msg.name(), None);
+        assert_eq!(msg.target(), None);
+        assert_eq!(msg.viewers(), None);
+        assert_eq!(
+            msg.clone().into_owned().raw(),
+            ":tmi.trovo.tv HOSTED #museun :hello\r\n"
+        );
+    }
+
+    #[test]
+    fn derive_errors() {
+        assert!(matches!(
+            parse(":tmi.trovo.tv HOSTING #museun :hello\r\n"),
+            Err(MessageError::InvalidCommand { .. })
+        ));
+        assert!(matches!(
+            parse(":tmi.trovo.tv HOSTED :hello\r\n"),
+            Err(MessageError::ExpectedArg { pos: 0 })
+        ));
+        assert!(matches!(
+            parse(":tmi.trovo.tv HOSTED #museun\r\n"),
+            Err(MessageError::ExpectedData)
+        ));
+        assert!(matches!(
+            parse("@viewers=many :tmi.trovo.tv HOSTED #museun :hello\r\n"),
+            Err(MessageError::CannotParseTag { .. })
+        ));
+    }
+
+    #[test]
+    fn derive_registry() {
+        let mut registry = Registry::new();
+        registry.register::<Hosted<'static>>("HOSTED");
+
+        let input = ":tmi.trovo.tv HOSTED #museun :hello\r\n";
+        let msg = registry
+            .parse(crate::irc::parse_one(input).unwrap().1)
+            .unwrap();
+        let msg = match msg {
+            Commands::Custom(msg) => msg,
+            _ => unreachable!(),
+        };
+        assert_eq!(msg.downcast_ref::<Hosted>().unwrap().channel(), "#museun");
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn derive_serde() {
+        let input =
+            "@viewers=10 :museun!museun@museun.tmi.trovo.tv HOSTED #museun #shaken_bot :hello\r\n";
+        crate::serde::round_trip_json::<Hosted>(input);
+        crate::serde::round_trip_rmp::<Hosted>(input);
+    }
+}
@@ -0,0 +1,475 @@
+/*!
+Aggregation of gifted subscriptions into a single event
+
+When a user gifts several subscriptions at once, Trovo sends a `submysterygift` [UserNotice] followed by a `subgift` [UserNotice] for each of the recipients.
+
+The [GiftBombAggregator] collects these follow-up gifts under their origin and produces a single [GiftBomb] once all of them have arrived, or once no more have arrived within its timeout. Every other message is passed through untouched.
+
+With the `async` feature, [GiftBombs] wraps any `Stream` of [Commands] (such as the [AsyncRunner](crate::AsyncRunner)) with an aggregator.
+*/
+
+use crate::messages::{Commands, SubGift, UserNotice, UserNoticeEvent};
+use std::time::{Duration, Instant};
+
+/// The default time to wait for the rest of the gifts
+pub const DEFAULT_GIFT_BOMB_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// An item produced by the [GiftBombAggregator]
+#[derive(Debug, Clone, PartialEq)]
+pub enum GiftBombEvent {
+    /// A message that wasn't part of a gift bomb
+    Message(Commands<'static>),
+    /// A collected gift bomb
+    GiftBomb(GiftBomb),
+}
+
+/// A `submysterygift` and the individual gifts that followed it
+#[derive(Debug, Clone, PartialEq)]
+pub struct GiftBomb {
+    origin: UserNotice<'static>,
+    gifts: Vec<UserNotice<'static>>,
+    count: u64,
+}
+
+impl GiftBomb {
+    /// The `submysterygift` notice that started this gift bomb
+    pub fn origin(&self) -> &UserNotice<'static> {
+        &self.origin
+    }
+
+    /// The individual `subgift` notices that were collected
+    pub fn gifts(&self) -> &[UserNotice<'static>] {
+        &self.gifts
+    }
+
+    /// The channel this gift bomb happened on
+    pub fn channel(&self) -> &str {
+        self.origin.channel()
+    }
+
+    /// The login of the gifter
+    pub fn gifter(&self) -> Option<&str> {
+        self.origin.login()
+    }
+
+    /// How many subscriptions were announced
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// How many subscriptions the gifter has gifted to this channel in total, if they shared it
+    pub fn total(&self) -> Option<u64> {
+        match self.origin.event() {
+            Ok(UserNoticeEvent::SubMysteryGift(gift)) => gift.total,
+            _ => None,
+        }
+    }
+
+    /// The recipients of the gifts that were collected
+    pub fn recipients(&self) -> impl Iterator<Item = SubGift<'_>> + '_ {
+        self.gifts.iter().filter_map(|msg| match msg.event() {
+            Ok(UserNoticeEvent::SubGift(gift)) | Ok(UserNoticeEvent::AnonSubGift(gift)) => {
+                Some(gift)
+            }
+            _ => None,
+        })
+    }
+
+    /// Whether all of the announced gifts were collected
+    ///
+    /// This is false if the timeout elapsed before the rest of the gifts arrived.
+    pub fn is_complete(&self) -> bool {
+        self.gifts.len() as u64 >= self.count
+    }
+}
+
+#[derive(Debug)]
+struct Pending {
+    bomb: GiftBomb,
+    deadline: Instant,
+}
+
+impl Pending {
+    fn matches(&self, msg: &UserNotice<'_>) -> bool {
+        const ORIGIN_ID: &str = "msg-param-origin-id";
+
+        if self.bomb.channel() != msg.channel() {
+            return false;
+        }
+
+        match (
+            self.bomb.origin.tags().get(ORIGIN_ID),
+            msg.tags().get(ORIGIN_ID),
+        ) {
+            (Some(left), Some(right)) => left == right,
+            _ => self.bomb.gifter() == msg.login(),
+        }
+    }
+}
+
+/**
+Collects the gifts that follow a `submysterygift` into a single [GiftBomb]
+
+Gifts are matched to their origin by the `msg-param-origin-id` tag when it is available, otherwise by the channel and the gifter.
+
+```
+# use trovochat::{gift_bomb::*, messages::Commands, FromIrcMessage as _};
+let input = "@login=museun;msg-id=submysterygift;msg-param-mass-gift-count=2;msg-param-sub-plan=1000 :tmi.trovo.tv USERNOTICE #shaken_bot\r\n\
+             @login=museun;msg-id=subgift;msg-param-months=1;msg-param-recipient-display-name=A;msg-param-recipient-id=1;msg-param-recipient-user-name=a;msg-param-sub-plan=1000 :tmi.trovo.tv USERNOTICE #shaken_bot\r\n\
+             @login=museun;msg-id=subgift;msg-param-months=1;msg-param-recipient-display-name=B;msg-param-recipient-id=2;msg-param-recipient-user-name=b;msg-param-sub-plan=1000 :tmi.trovo.tv USERNOTICE #shaken_bot\r\n";
+
+let mut aggregator = GiftBombAggregator::default();
+let mut events = trovochat::irc::parse(input)
+    .map(|msg| Commands::from_irc(msg.unwrap()).unwrap())
+    .filter_map(|msg| aggregator.push(msg));
+
+match events.next().unwrap() {
+    GiftBombEvent::GiftBomb(bomb) => {
+        assert_eq!(bomb.gifter(), Some("museun"));
+        assert!(bomb.is_complete());
+        let recipients = bomb.recipients().map(|gift| gift.recipient_login).collect::<Vec<_>>();
+        assert_eq!(recipients, vec!["a", "b"]);
+    }
+    _ => unreachable!(),
+}
+assert!(events.next().is_none());
+```
+*/
+#[derive(Debug)]
+pub struct GiftBombAggregator {
+    timeout: Duration,
+    pending: Vec<Pending>,
+}
+
+impl Default for GiftBombAggregator {
+    fn default() -> Self {
+        Self::new(DEFAULT_GIFT_BOMB_TIMEOUT)
+    }
+}
+
+impl GiftBombAggregator {
+    /// Create a new aggregator that waits at most `timeout` between gifts
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            pending: vec![],
+        }
+    }
+
+    /// Get the time this waits between gifts
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Push a message into the aggregator
+    ///
+    /// This returns the message if it isn't part of a gift bomb, or the [GiftBomb] once this message completes it.
+    pub fn push(&mut self, msg: Commands<'static>) -> Option<GiftBombEvent> {
+        let msg = match msg {
+            Commands::UserNotice(msg) => msg,
+            msg => return Some(GiftBombEvent::Message(msg)),
+        };
+
+        match msg.event() {
+            Ok(UserNoticeEvent::SubMysteryGift(gift)) if gift.count > 0 => {
+                let count = gift.count;
+                self.pending.push(Pending {
+                    bomb: GiftBomb {
+                        origin: msg,
+                        gifts: Vec::new(),
+                        count,
+                    },
+                    deadline: Instant::now() + self.timeout,
+                });
+                None
+            }
+
+            Ok(UserNoticeEvent::SubGift(..)) | Ok(UserNoticeEvent::AnonSubGift(..)) => {
+                let pos = match self.pending.iter().position(|p| p.matches(&msg)) {
+                    Some(pos) => pos,
+                    None => return Some(GiftBombEvent::Message(Commands::UserNotice(msg))),
+                };
+
+                let pending = &mut self.pending[pos];
+                pending.bomb.gifts.push(msg);
+                pending.deadline = Instant::now() + self.timeout;
+
+                if pending.bomb.is_complete() {
+                    let pending = self.pending.remove(pos);
+                    return Some(GiftBombEvent::GiftBomb(pending.bomb));
+                }
+                None
+            }
+
+            _ => Some(GiftBombEvent::Message(Commands::UserNotice(msg))),
+        }
+    }
+
+    /// Take the next gift bomb that has waited longer than the timeout, as of `now`
+    pub fn expired(&mut self, now: Instant) -> Option<GiftBomb> {
+        let pos = self.pending.iter().position(|p| p.deadline <= now)?;
+        Some(self.pending.remove(pos).bomb)
+    }
+
+    /// When the next pending gift bomb will expire, if there are any
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.pending.iter().map(|p| p.deadline).min()
+    }
+
+    /// Take the next pending gift bomb, regardless of its timeout
+    ///
+    /// This is useful when the stream of messages has ended.
+    pub fn flush(&mut self) -> Option<GiftBomb> {
+        if self.pending.is_empty() {
+            return None;
+        }
+        Some(self.pending.remove(0).bomb)
+    }
+
+    /// Whether there are any pending gift bombs
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+cfg_async! {
+    pin_project_lite::pin_project! {
+        /**
+        A `Stream` adapter that aggregates gift bombs from a `Stream` of [Commands]
+
+        This yields every other message as it arrives, and each [GiftBomb] once it is complete or has timed out.
+
+        When the inner stream ends, any pending gift bombs are yielded before this ends.
+        */
+        pub struct GiftBombs<S> {
+            #[pin]
+            stream: S,
+            aggregator: GiftBombAggregator,
+            delay: Option<futures_timer::Delay>,
+            done: bool,
+        }
+    }
+
+    impl<S> std::fmt::Debug for GiftBombs<S> {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("GiftBombs")
+                .field("aggregator", &self.aggregator)
+                .field("done", &self.done)
+                .finish()
+        }
+    }
+
+    impl<S> GiftBombs<S> {
+        /// Wrap this stream with the default timeout
+        pub fn new(stream: S) -> Self {
+            Self::with_aggregator(stream, GiftBombAggregator::default())
+        }
+
+        /// Wrap this stream with this aggregator
+        pub fn with_aggregator(stream: S, aggregator: GiftBombAggregator) -> Self {
+            Self {
+                stream,
+                aggregator,
+                delay: None,
+                done: false,
+            }
+        }
+
+        /// Get the inner stream back
+        ///
+        /// Any pending gift bombs are dropped.
+        pub fn into_inner(self) -> S {
+            self.stream
+        }
+    }
+
+    impl<S> futures_lite::Stream for GiftBombs<S>
+    where
+        S: futures_lite::Stream<Item = Commands<'static>>,
+    {
+        type Item = GiftBombEvent;
+
+        fn poll_next(
+            self: std::pin::Pin<&mut Self>,
+            cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<Option<Self::Item>> {
+            use std::{future::Future as _, pin::Pin, task::Poll};
+
+            let mut this = self.project();
+
+            loop {
+                if !*this.done {
+                    match this.stream.as_mut().poll_next(cx) {
+                        Poll::Ready(Some(msg)) => match this.aggregator.push(msg) {
+                            Some(event) => return Poll::Ready(Some(event)),
+                            None => continue,
+                        },
+                        Poll::Ready(None) => *this.done = true,
+                        Poll::Pending => {}
+                    }
+                }
+
+                if *this.done {
+                    let bomb = this.aggregator.flush();
+                    return Poll::Ready(bomb.map(GiftBombEvent::GiftBomb));
+                }
+
+                let now = Instant::now();
+                if let Some(bomb) = this.aggregator.expired(now) {
+                    return Poll::Ready(Some(GiftBombEvent::GiftBomb(bomb)));
+                }
+
+                let deadline = match this.aggregator.next_deadline() {
+                    Some(deadline) => deadline,
+                    None => return Poll::Pending,
+                };
+
+                let wait = deadline.saturating_duration_since(now);
+                let delay = this
+                    .delay
+                    .get_or_insert_with(|| futures_timer::Delay::new(wait));
+                delay.reset(wait);
+
+                if Pin::new(delay).poll(cx).is_pending() {
+                    return Poll::Pending;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FromIrcMessage as _;
+
+    fn gift(login: &str, recipient: &str, origin: Option<&str>) -> Commands<'static> {
+        let origin = origin
+            .map(|id| format!("msg-param-origin-id={};", id))
+            .unwrap_or_default();
+        let input = format!(
+            "@login={};msg-id=subgift;{}msg-param-months=1;msg-param-recipient-display-name={};msg-param-recipient-id=1;msg-param-recipient-user-name={};msg-param-sub-plan=1000 :tmi.trovo.tv USERNOTICE #museun\r\n",
+            login, origin, recipient, recipient
+        );
+        parse(&input)
+    }
+
+    fn mystery(login: &str, count: u64, origin: Option<&str>) -> Commands<'static> {
+        let origin = origin
+            .map(|id| format!("msg-param-origin-id={};", id))
+            .unwrap_or_default();
+        let input = format!(
+            "@login={};msg-id=submysterygift;msg-param-mass-gift-count={};{}msg-param-sub-plan=1000 :tmi.trovo.tv USERNOTICE #museun\r\n",
+            login, count, origin
+        );
+        parse(&input)
+    }
+
+    fn parse(input: &str) -> Commands<'static> {
+        let msg = crate::irc::parse_one(input).unwrap().1;
+        crate::IntoOwned::into_owned(Commands::from_irc(msg).unwrap())
+    }
+
+    fn unwrap_bomb(event: Option<GiftBombEvent>) -> GiftBomb {
+        match event {
+            Some(GiftBombEvent::GiftBomb(bomb)) => bomb,
+            event => panic!("expected a gift bomb, got: {:?}", event),
+        }
+    }
+
+    #[test]
+    fn aggregate() {
+        let mut agg = GiftBombAggregator::default();
+
+        assert!(agg.push(mystery("museun", 2, None)).is_none());
+        assert!(agg.push(gift("museun", "a", None)).is_none());
+
+        // unrelated messages are passed through
+        let ping = parse("PING :1234\r\n");
+        assert_eq!(agg.push(ping.clone()), Some(GiftBombEvent::Message(ping)));
+
+        let other = gift("someone_else", "c", None);
+        assert_eq!(agg.push(other.clone()), Some(GiftBombEvent::Message(other)));
+
+        let bomb = unwrap_bomb(agg.push(gift("museun", "b", None)));
+        assert!(bomb.is_complete());
+        assert_eq!(bomb.count(), 2);
+        assert_eq!(bomb.gifter(), Some("museun"));
+        assert_eq!(bomb.channel(), "#museun");
+        assert_eq!(
+            bomb.recipients()
+                .map(|gift| gift.recipient_login)
+                .collect::<Vec<_>>(),
+            vec!["a", "b"]
+        );
+        assert!(agg.is_empty());
+    }
+
+    #[test]
+    fn aggregate_by_origin_id() {
+        let mut agg = GiftBombAggregator::default();
+
+        assert!(agg
+            .push(mystery("ananonymousgifter", 1, Some("1")))
+            .is_none());
+        assert!(agg
+            .push(mystery("ananonymousgifter", 1, Some("2")))
+            .is_none());
+
+        let bomb = unwrap_bomb(agg.push(gift("ananonymousgifter", "b", Some("2"))));
+        assert_eq!(bomb.origin().tags().get("msg-param-origin-id"), Some("2"));
+
+        let bomb = unwrap_bomb(agg.push(gift("ananonymousgifter", "a", Some("1"))));
+        assert_eq!(bomb.origin().tags().get("msg-param-origin-id"), Some("1"));
+    }
+
+    #[test]
+    fn aggregate_timeout() {
+        let timeout = Duration::from_secs(5);
+        let mut agg = GiftBombAggregator::new(timeout);
+
+        assert!(agg.push(mystery("museun", 3, None)).is_none());
+        assert!(agg.push(gift("museun", "a", None)).is_none());
+
+        assert!(agg.expired(Instant::now()).is_none());
+        assert!(agg.next_deadline().is_some());
+
+        let bomb = agg.expired(Instant::now() + timeout).unwrap();
+        assert!(!bomb.is_complete());
+        assert_eq!(bomb.count(), 3);
+        assert_eq!(bomb.gifts().len(), 1);
+
+        assert!(agg.is_empty());
+        assert!(agg.next_deadline().is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn gift_bombs_stream() {
+        use futures_lite::StreamExt as _;
+
+        let ping = parse("PING :1234\r\n");
+        let messages = vec![
+            mystery("museun", 2, None),
+            gift("museun", "a", None),
+            ping.clone(),
+            gift("museun", "b", None),
+            mystery("museun", 5, None),
+            gift("museun", "c", None),
+        ];
+
+        let stream = GiftBombs::new(futures_lite::stream::iter(messages));
+        let events = futures_lite::future::block_on(stream.collect::<Vec<_>>());
+
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0], GiftBombEvent::Message(ping));
+
+        let bomb = unwrap_bomb(Some(events[1].clone()));
+        assert!(bomb.is_complete());
+
+        // the stream ended before the rest of these arrived
+        let bomb = unwrap_bomb(Some(events[2].clone()));
+        assert!(!bomb.is_complete());
+        assert_eq!(bomb.gifts().len(), 1);
+    }
+}
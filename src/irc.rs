@@ -24,6 +24,8 @@
 //! // and unwrap whether it was an invalid message or not
 //! messages.pop().unwrap();
 //! ```
+//!
+//! You can also parse from a `&[u8]` with [parse_one_bytes]. This only validates UTF-8 for the parts of the message you look at.
 use crate::MaybeOwned;
 
 /// A trait to convert an `IrcMessage` into `Self`.
@@ -62,7 +64,10 @@ mod prefix;
 pub use prefix::{Prefix, PrefixIndex};
 
 pub(crate) mod tags;
-pub use tags::{Tags, TagsIter};
+pub use tags::{escape_str, unescape_str, Tags, TagsIter};
+
+mod tag_map;
+pub use tag_map::TagMap;
 
 mod tag_indices;
 pub use tag_indices::TagIndices;
@@ -71,7 +76,16 @@ mod error;
 pub use error::MessageError;
 
 mod parser;
-pub use parser::IrcParserIter;
+pub use parser::{IrcParserIter, ParseMode};
+
+mod bytes;
+pub use bytes::IrcMessageBytes;
+
+mod builder;
+pub use builder::{IrcMessageBuilder, IrcMessageBuilderError};
+
+mod reencode;
+pub use reencode::Reencode;
 
 /// Parses a string and returns an iterator over the `IrcMessages` in it.
 ///
@@ -84,18 +98,56 @@ pub fn parse(input: &str) -> IrcParserIter<'_> {
 ///
 /// This returns the index of the /next/ message (e.g, 0 for a single message) and the parsed message
 pub fn parse_one(input: &str) -> Result<(usize, IrcMessage<'_>), MessageError> {
-    const CRLF: &str = "\r\n";
+    parse_one_with_mode(input, ParseMode::Lenient)
+}
+
+/// Attempts to parse one message, with the provided [ParseMode].
+///
+/// This returns the index of the /next/ message (e.g, 0 for a single message) and the parsed message
+pub fn parse_one_with_mode(
+    input: &str,
+    mode: ParseMode,
+) -> Result<(usize, IrcMessage<'_>), MessageError> {
+    let (pos, next) = split_line(input.as_bytes())?;
+    let msg = IrcMessage::parse_with_mode(MaybeOwned::Borrowed(&input[..next]), mode)?;
+    Ok((pos, msg))
+}
+
+/// Attempts to parse one message from bytes.
+///
+/// This returns the index of the /next/ message (e.g, 0 for a single message) and the parsed message.
+///
+/// UTF-8 isn't validated until you access parts of the message. See [IrcMessageBytes].
+pub fn parse_one_bytes(input: &[u8]) -> Result<(usize, IrcMessageBytes<'_>), MessageError> {
+    parse_one_bytes_with_mode(input, ParseMode::Lenient)
+}
+
+/// Attempts to parse one message from bytes, with the provided [ParseMode].
+///
+/// This returns the index of the /next/ message (e.g, 0 for a single message) and the parsed message.
+///
+/// UTF-8 isn't validated until you access parts of the message. See [IrcMessageBytes].
+pub fn parse_one_bytes_with_mode(
+    input: &[u8],
+    mode: ParseMode,
+) -> Result<(usize, IrcMessageBytes<'_>), MessageError> {
+    let (pos, next) = split_line(input)?;
+    let msg = IrcMessageBytes::parse_with_mode(&input[..next], mode)?;
+    Ok((pos, msg))
+}
+
+// returns the index of the next message (or 0 if this was the last one) and the end of this one
+fn split_line(input: &[u8]) -> Result<(usize, usize), MessageError> {
+    const CRLF: &[u8] = b"\r\n";
 
     let pos = input
-        .find(CRLF)
+        .windows(CRLF.len())
+        .position(|window| window == CRLF)
         .ok_or(MessageError::IncompleteMessage { pos: 0 })?
         + CRLF.len();
 
-    let next = &input[..pos];
-    let done = next.len() == input.len();
-
-    let msg = IrcMessage::parse(crate::MaybeOwned::Borrowed(next))?;
-    Ok((if done { 0 } else { pos }, msg))
+    let done = pos == input.len();
+    Ok((if done { 0 } else { pos }, pos))
 }
 
 // TODO add a test for parse_one. it was wrong
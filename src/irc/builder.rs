@@ -0,0 +1,321 @@
+use super::{parser::Parser, IrcMessage, MessageError, ParseMode, TagMap};
+use crate::MaybeOwned;
+
+/// An error returned by the [IrcMessageBuilder]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum IrcMessageBuilderError {
+    /// The command wasn't letters or a 3-digit numeric
+    InvalidCommand {
+        /// The provided command
+        command: String,
+    },
+    /// An argument was empty, started with a `:` or contained a space, `CR`, `LF` or `NUL`
+    InvalidArg {
+        /// The position of the argument
+        pos: usize,
+        /// The provided argument
+        arg: String,
+    },
+    /// The data contained a `CR`, `LF` or `NUL`
+    InvalidData,
+    /// A tag key didn't follow the IRCv3 grammar
+    InvalidTagKey {
+        /// The provided key
+        key: String,
+    },
+    /// The tags contained a space, `CR`, `LF` or `NUL`
+    InvalidTags {
+        /// The provided tags
+        tags: String,
+    },
+    /// The prefix was empty or contained a space, `CR`, `LF` or `NUL`
+    InvalidPrefix {
+        /// The provided prefix
+        prefix: String,
+    },
+    /// The built message could not be parsed
+    ParseError(MessageError),
+}
+
+impl std::fmt::Display for IrcMessageBuilderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidCommand { command } => write!(f, "invalid command: '{}'", command),
+            Self::InvalidArg { pos, arg } => write!(f, "invalid arg at {}: '{}'", pos, arg),
+            Self::InvalidData => f.write_str("invalid data: it cannot contain CR, LF or NUL"),
+            Self::InvalidTagKey { key } => write!(f, "invalid tag key: '{}'", key),
+            Self::InvalidTags { tags } => write!(f, "invalid tags: '{}'", tags),
+            Self::InvalidPrefix { prefix } => write!(f, "invalid prefix: '{}'", prefix),
+            Self::ParseError(err) => write!(f, "cannot parse the message: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for IrcMessageBuilderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::ParseError(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/**
+A builder for constructing an [IrcMessage]
+
+This is useful for testing, or for proxying messages.
+
+Tag values are escaped, so you should provide them unescaped.
+
+```
+# use trovochat::{irc::IrcMessage, messages::Privmsg, FromIrcMessage as _};
+let msg = IrcMessage::builder("PRIVMSG")
+    .tag("display-name", "Museun")
+    .tag("my-message", "spans\nmultiple lines")
+    .user("museun")
+    .arg("#museun")
+    .data("hello world")
+    .build()
+    .unwrap();
+
+assert_eq!(
+    msg.get_raw(),
+    "@display-name=Museun;my-message=spans\\nmultiple\\slines \
+     :museun!museun@museun.tmi.trovo.tv PRIVMSG #museun :hello world\r\n"
+);
+
+// it can be converted into any of the typed messages
+let pm = Privmsg::from_irc(msg).unwrap();
+assert_eq!(pm.name(), "museun");
+assert_eq!(pm.channel(), "#museun");
+assert_eq!(pm.data(), "hello world");
+assert_eq!(pm.display_name().unwrap(), "Museun");
+```
+*/
+#[derive(Debug, Clone)]
+pub struct IrcMessageBuilder {
+    command: String,
+    tags: TagMap,
+    prefix: Option<String>,
+    args: Vec<String>,
+    data: Option<String>,
+}
+
+impl IrcMessageBuilder {
+    /// Create a new builder for this command
+    pub fn new(command: impl Into<String>) -> Self {
+        Self {
+            command: command.into(),
+            tags: TagMap::new(),
+            prefix: None,
+            args: vec![],
+            data: None,
+        }
+    }
+
+    /// Add this tag, with an unescaped value
+    ///
+    /// This replaces any previous tag with the same key
+    pub fn tag(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.tags.insert(key, value);
+        self
+    }
+
+    /// Add all of these tags
+    ///
+    /// This replaces any previous tags with the same keys
+    pub fn tags(mut self, tags: TagMap) -> Self {
+        self.tags.merge(tags);
+        self
+    }
+
+    /// Set the raw prefix, e.g. `tmi.trovo.tv` or `museun!museun@museun.tmi.trovo.tv`
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix.replace(prefix.into());
+        self
+    }
+
+    /// Set the prefix to a user prefix for this nickname
+    pub fn user(self, nick: &str) -> Self {
+        self.prefix(format!("{nick}!{nick}@{nick}.tmi.trovo.tv", nick = nick))
+    }
+
+    /// Add an argument
+    pub fn arg(mut self, arg: impl Into<String>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    /// Set the trailing data
+    pub fn data(mut self, data: impl Into<String>) -> Self {
+        self.data.replace(data.into());
+        self
+    }
+
+    /// Build the message
+    ///
+    /// # Errors
+    /// If the command, any argument, any tag key, the prefix or the data is invalid an error will be returned.
+    pub fn build(self) -> Result<IrcMessage<'static>, IrcMessageBuilderError> {
+        use std::fmt::Write as _;
+        type Error = IrcMessageBuilderError;
+
+        let is_forbidden = |c: char| matches!(c, '\0' | '\r' | '\n');
+
+        let is_numeric =
+            self.command.len() == 3 && self.command.bytes().all(|c| c.is_ascii_digit());
+        let is_word =
+            !self.command.is_empty() && self.command.bytes().all(|c| c.is_ascii_alphabetic());
+        if !is_numeric && !is_word {
+            return Err(Error::InvalidCommand {
+                command: self.command,
+            });
+        }
+
+        let mut buf = String::new();
+
+        if let Some((key, _)) = self
+            .tags
+            .iter()
+            .find(|(key, _)| Parser::validate_tag(key.as_bytes(), 0).is_err())
+        {
+            return Err(Error::InvalidTagKey {
+                key: key.to_string(),
+            });
+        }
+        if !self.tags.is_empty() {
+            let _ = write!(buf, "@{} ", self.tags);
+        }
+
+        if let Some(prefix) = &self.prefix {
+            if prefix.is_empty() || prefix.contains(|c| c == ' ' || is_forbidden(c)) {
+                return Err(Error::InvalidPrefix {
+                    prefix: prefix.clone(),
+                });
+            }
+            let _ = write!(buf, ":{} ", prefix);
+        }
+
+        buf.push_str(&self.command);
+
+        for (pos, arg) in self.args.iter().enumerate() {
+            if arg.is_empty()
+                || arg.starts_with(':')
+                || arg.contains(|c| c == ' ' || is_forbidden(c))
+            {
+                return Err(Error::InvalidArg {
+                    pos,
+                    arg: arg.clone(),
+                });
+            }
+            let _ = write!(buf, " {}", arg);
+        }
+
+        if let Some(data) = &self.data {
+            if data.contains(is_forbidden) {
+                return Err(Error::InvalidData);
+            }
+            let _ = write!(buf, " :{}", data);
+        }
+
+        buf.push_str("\r\n");
+
+        IrcMessage::parse_with_mode(MaybeOwned::Shared(buf.into()), ParseMode::Strict)
+            .map_err(Error::ParseError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{messages::Commands, FromIrcMessage as _, IntoOwned as _};
+
+    #[test]
+    fn build_minimal() {
+        let msg = IrcMessage::builder("PING").build().unwrap();
+        assert_eq!(msg.get_raw(), "PING\r\n");
+        assert_eq!(msg.get_command(), "PING");
+        assert!(msg.get_tags().is_none());
+        assert!(msg.get_prefix().is_none());
+        assert!(msg.get_args().is_none());
+        assert!(msg.get_data().is_none());
+    }
+
+    #[test]
+    fn build_same_as_parsed() {
+        let input =
+            "@+example.com/foo=a\\sb;badge-info= :tmi.trovo.tv CAP * ACK :trovo.tv/tags\r\n";
+        let expected = crate::irc::parse_one(input).unwrap().1.into_owned();
+
+        let msg = IrcMessage::builder("CAP")
+            .tag("+example.com/foo", "a b")
+            .tag("badge-info", "")
+            .prefix("tmi.trovo.tv")
+            .arg("*")
+            .arg("ACK")
+            .data("trovo.tv/tags")
+            .build()
+            .unwrap();
+
+        assert_eq!(msg, expected);
+        assert!(matches!(
+            Commands::from_irc(msg).unwrap(),
+            Commands::Cap(..)
+        ));
+    }
+
+    #[test]
+    fn build_replaces_tags() {
+        let msg = IrcMessage::builder("PING")
+            .tag("a", "1")
+            .tag("b", "2")
+            .tag("a", "3")
+            .build()
+            .unwrap();
+        assert_eq!(msg.get_raw(), "@a=3;b=2 PING\r\n");
+    }
+
+    #[test]
+    fn build_errors() {
+        use IrcMessageBuilderError as E;
+
+        fn kind(err: &E) -> &'static str {
+            match err {
+                E::InvalidCommand { .. } => "command",
+                E::InvalidArg { .. } => "arg",
+                E::InvalidData => "data",
+                E::InvalidTagKey { .. } => "tag",
+                E::InvalidTags { .. } => "tags",
+                E::InvalidPrefix { .. } => "prefix",
+                E::ParseError(..) => "parse",
+            }
+        }
+
+        let tests = vec![
+            (IrcMessage::builder(""), "command"),
+            (IrcMessage::builder("PRIV MSG"), "command"),
+            (IrcMessage::builder("12"), "command"),
+            (IrcMessage::builder("PING").arg(""), "arg"),
+            (IrcMessage::builder("PING").arg("a").arg("b c"), "arg"),
+            (IrcMessage::builder("PING").arg(":a"), "arg"),
+            (IrcMessage::builder("PING").data("a\r\nb"), "data"),
+            (IrcMessage::builder("PING").tag("a b", ""), "tag"),
+            (IrcMessage::builder("PING").tag("", "a"), "tag"),
+            (IrcMessage::builder("PING").prefix(""), "prefix"),
+            (IrcMessage::builder("PING").prefix("a b"), "prefix"),
+        ];
+
+        for (builder, expected) in tests {
+            let err = builder.clone().build().unwrap_err();
+            assert_eq!(kind(&err), expected, "{:?} -> {:?}", builder, err);
+        }
+
+        let err = IrcMessage::builder("PING")
+            .arg("a")
+            .arg("b c")
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, E::InvalidArg { pos: 1, .. }));
+    }
+}
@@ -0,0 +1,189 @@
+use super::{
+    parser::{Indices, Parser},
+    IrcMessage, MessageError, ParseMode, PrefixIndex,
+};
+use crate::{MaybeOwned, MaybeOwnedIndex};
+use std::str::Utf8Error;
+
+/// A raw irc message `@tags :prefix COMMAND args :data\r\n`, borrowed from bytes
+///
+/// This uses the same indices as an [IrcMessage], but UTF-8 is only validated for the parts you access.
+///
+/// ```
+/// # use trovochat::irc::IrcMessageBytes;
+/// let input = b":test!test@test PRIVMSG #museun :\xF0\x90\x80 invalid\r\n";
+/// let msg = IrcMessageBytes::parse(input).unwrap();
+///
+/// assert_eq!(msg.get_command().unwrap(), "PRIVMSG");
+/// assert_eq!(msg.nth_arg(0).unwrap().unwrap(), "#museun");
+/// // the data isn't valid UTF-8
+/// assert!(msg.get_data().unwrap().is_err());
+/// // so it cannot be turned into an IrcMessage
+/// assert!(msg.to_irc_message().is_err());
+/// ```
+#[derive(Copy, Clone, PartialEq)]
+pub struct IrcMessageBytes<'a> {
+    /// The raw bytes
+    pub raw: &'a [u8],
+    /// Index of the tags
+    pub tags: Option<MaybeOwnedIndex>,
+    /// Index of the prefix
+    pub prefix: Option<PrefixIndex>,
+    /// Index of the command
+    pub command: MaybeOwnedIndex,
+    /// Index of the args
+    pub args: Option<MaybeOwnedIndex>,
+    /// Index of the data
+    pub data: Option<MaybeOwnedIndex>,
+}
+
+impl<'a> IrcMessageBytes<'a> {
+    /// Parse a single message from these bytes
+    ///
+    /// This does not validate UTF-8
+    pub fn parse(input: &'a [u8]) -> Result<Self, MessageError> {
+        Self::parse_with_mode(input, ParseMode::Lenient)
+    }
+
+    /// Parse a single message from these bytes, with the provided [ParseMode]
+    ///
+    /// This does not validate UTF-8
+    pub fn parse_with_mode(input: &'a [u8], mode: ParseMode) -> Result<Self, MessageError> {
+        let Indices {
+            tags,
+            prefix,
+            command,
+            args,
+            data,
+        } = Parser::parse(input, mode)?;
+
+        Ok(Self {
+            raw: input,
+            tags,
+            prefix,
+            command,
+            args,
+            data,
+        })
+    }
+
+    /// Converts this into an [IrcMessage] by validating all of it as UTF-8
+    ///
+    /// This reuses the parsed indices, it does not parse the message again.
+    pub fn to_irc_message(&self) -> Result<IrcMessage<'a>, Utf8Error> {
+        let raw = std::str::from_utf8(self.raw)?;
+        Ok(IrcMessage {
+            raw: MaybeOwned::Borrowed(raw),
+            tags: self.tags,
+            prefix: self.prefix,
+            command: self.command,
+            args: self.args,
+            data: self.data,
+        })
+    }
+
+    /// Get the raw bytes
+    pub fn get_raw(&self) -> &'a [u8] {
+        self.raw
+    }
+
+    /// Get the raw tags
+    pub fn get_tags(&self) -> Option<Result<&'a str, Utf8Error>> {
+        self.tags.map(|index| self.get(index))
+    }
+
+    /// Get the raw prefix
+    pub fn get_prefix(&self) -> Option<Result<&'a str, Utf8Error>> {
+        self.prefix.map(|index| self.get(index.as_index()))
+    }
+
+    /// Get the raw command
+    pub fn get_command(&self) -> Result<&'a str, Utf8Error> {
+        self.get(self.command)
+    }
+
+    /// Get the raw args
+    pub fn get_args(&self) -> Option<Result<&'a str, Utf8Error>> {
+        self.args.map(|index| self.get(index))
+    }
+
+    /// Get the raw data
+    pub fn get_data(&self) -> Option<Result<&'a str, Utf8Error>> {
+        self.data.map(|index| self.get(index))
+    }
+
+    /// Get the raw 'nth' argument
+    pub fn nth_arg(&self, nth: usize) -> Option<Result<&'a str, Utf8Error>> {
+        self.args
+            .map(|index| &self.raw[index.as_range()])?
+            .split(u8::is_ascii_whitespace)
+            .filter(|s| !s.is_empty())
+            .nth(nth)
+            .map(std::str::from_utf8)
+    }
+
+    fn get(&self, index: MaybeOwnedIndex) -> Result<&'a str, Utf8Error> {
+        std::str::from_utf8(&self.raw[index.as_range()])
+    }
+}
+
+impl<'a> std::fmt::Debug for IrcMessageBytes<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let lossy = |index: MaybeOwnedIndex| String::from_utf8_lossy(&self.raw[index.as_range()]);
+
+        f.debug_struct("IrcMessageBytes")
+            .field("raw", &String::from_utf8_lossy(self.raw))
+            .field("tags", &self.tags.map(lossy))
+            .field("prefix", &self.prefix.map(|index| lossy(index.as_index())))
+            .field("command", &lossy(self.command))
+            .field("args", &self.args.map(lossy))
+            .field("data", &self.data.map(lossy))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_as_str() {
+        let inputs: &[&str] = &[
+            ":test!test@test PRIVMSG #museun :this is a test\r\n",
+            "@badge-info=;color=#FF69B4 :tmi.trovo.tv USERSTATE #museun\r\n",
+            ":tmi.trovo.tv CAP * ACK :trovo.tv/membership\r\n",
+            "PING :1234567890\r\n",
+            "RECONNECT\r\n",
+        ];
+
+        for input in inputs {
+            let left = IrcMessage::parse(MaybeOwned::Borrowed(input)).unwrap();
+            let right = IrcMessageBytes::parse(input.as_bytes()).unwrap();
+            assert_eq!(right.to_irc_message().unwrap(), left);
+            assert_eq!(right.get_command().unwrap(), left.get_command());
+            assert_eq!(right.nth_arg(0).transpose().unwrap(), left.nth_arg(0));
+            assert_eq!(right.get_data().transpose().unwrap(), left.get_data());
+        }
+    }
+
+    #[test]
+    fn lazy_utf8() {
+        let input = b"@foo=\xFF :test!test@test PRIVMSG #museun :\xFF\r\n";
+        let msg = IrcMessageBytes::parse(input).unwrap();
+        assert_eq!(msg.get_command().unwrap(), "PRIVMSG");
+        assert_eq!(msg.get_prefix().unwrap().unwrap(), "test");
+        assert_eq!(msg.nth_arg(0).unwrap().unwrap(), "#museun");
+        assert!(msg.get_tags().unwrap().is_err());
+        assert!(msg.get_data().unwrap().is_err());
+        assert!(msg.to_irc_message().is_err());
+    }
+
+    #[test]
+    fn parse_empty_spaces() {
+        for i in 0..10 {
+            let s = format!("{}\r\n", " ".repeat(i));
+            let err = IrcMessageBytes::parse(s.as_bytes()).unwrap_err();
+            assert!(matches!(err, MessageError::EmptyMessage))
+        }
+    }
+}
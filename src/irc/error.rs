@@ -45,6 +45,14 @@ pub enum MessageError {
     /// An empty message was provided
     EmptyMessage,
 
+    /// The message did not follow the grammar
+    InvalidSyntax {
+        /// The byte offset into the message where the error was found
+        pos: usize,
+        /// A description of what was expected at that position
+        expected: &'static str,
+    },
+
     /// A custom error message
     Custom {
         /// The inner error
@@ -65,6 +73,9 @@ impl std::fmt::Display for MessageError {
             Self::CannotParseTag { name, error } => write!(f, "cannot parse '{}': {}", name, error),
             Self::IncompleteMessage { pos } => write!(f, "incomplete message starting at: {}", pos),
             Self::EmptyMessage => write!(f, "no message could be parsed"),
+            Self::InvalidSyntax { pos, expected } => {
+                write!(f, "invalid syntax at byte {}: expected {}", pos, expected)
+            }
             Self::Custom { error } => write!(f, "custom error: {}", error),
         }
     }
@@ -1,4 +1,7 @@
-use super::{parser::Parser, MessageError, Prefix, PrefixIndex};
+use super::{
+    parser::{Indices, Parser},
+    MessageError, ParseMode, Prefix, PrefixIndex,
+};
 use crate::{FromIrcMessage, MaybeOwned, MaybeOwnedIndex};
 use std::convert::Infallible;
 
@@ -21,32 +24,36 @@ pub struct IrcMessage<'a> {
 
 impl<'a> IrcMessage<'a> {
     pub(crate) fn parse(input: MaybeOwned<'a>) -> Result<Self, MessageError> {
-        // trim any \r\n off incase this was directly called
-        let data = if input.ends_with("\r\n") {
-            &input.as_ref()[..input.len() - 2]
-        } else {
-            input.as_ref()
-        };
-
-        let data = data.trim();
-        if data.is_empty() {
-            return Err(super::MessageError::EmptyMessage);
-        }
+        Self::parse_with_mode(input, ParseMode::Lenient)
+    }
 
-        let mut p = Parser {
-            input: data,
-            pos: 0,
-        };
+    pub(crate) fn parse_with_mode(
+        input: MaybeOwned<'a>,
+        mode: ParseMode,
+    ) -> Result<Self, MessageError> {
+        let Indices {
+            tags,
+            prefix,
+            command,
+            args,
+            data,
+        } = Parser::parse(input.as_bytes(), mode)?;
+
+        Ok(Self {
+            raw: input,
+            tags,
+            prefix,
+            command,
+            args,
+            data,
+        })
+    }
 
-        let this = Self {
-            tags: p.tags(),
-            prefix: p.prefix(),
-            command: p.command(),
-            args: p.args(),
-            data: p.data(),
-            raw: input, // NOTE: this stores the original input string, not the trimmed string
-        };
-        Ok(this)
+    /// Create a builder for a message with this command
+    ///
+    /// See [IrcMessageBuilder](super::IrcMessageBuilder)
+    pub fn builder(command: impl Into<String>) -> super::IrcMessageBuilder {
+        super::IrcMessageBuilder::new(command)
     }
 
     /// Get the raw string
@@ -54,6 +61,13 @@ impl<'a> IrcMessage<'a> {
         &*self.raw
     }
 
+    /// Re-encode this message in its canonical form, optionally rewriting its tags or prefix
+    ///
+    /// See [Reencode](super::Reencode)
+    pub fn reencode(&self) -> super::Reencode<'_> {
+        super::Reencode::new(self.get_raw())
+    }
+
     /// Get the raw tags
     pub fn get_tags(&self) -> Option<&str> {
         self.tags.map(|index| &self.raw[index])
@@ -98,27 +112,32 @@ impl<'a> IrcMessage<'a> {
         let args = &self.raw[index];
 
         let mut seen = 0;
-        let (mut head, mut tail) = (index.start, index.start);
-
-        for ch in args.chars() {
-            if ch.is_ascii_whitespace() {
-                if seen == nth {
-                    return Some(MaybeOwnedIndex::raw(head as _, tail as _));
+        let mut head = None;
+
+        for (i, ch) in args.char_indices() {
+            match (ch.is_ascii_whitespace(), head) {
+                // the end of an argument
+                (true, Some(start)) => {
+                    if seen == nth {
+                        let start = index.start as usize + start;
+                        return Some(MaybeOwnedIndex::raw(start, index.start as usize + i));
+                    }
+                    head = None;
+                    seen += 1;
                 }
-
-                // skip the space
-                head = tail + 1;
-                seen += 1;
+                // the start of an argument
+                (false, None) => head = Some(i),
+                _ => {}
             }
-
-            tail += 1;
         }
 
-        if seen == nth {
-            return Some(MaybeOwnedIndex::raw(head as _, tail as _));
+        match head {
+            Some(start) if seen == nth => Some(MaybeOwnedIndex::raw(
+                index.start as usize + start,
+                index.end as usize,
+            )),
+            _ => None,
         }
-
-        None
     }
 }
 
@@ -127,10 +146,26 @@ impl<'a> IrcMessage<'a> {
     ///
     /// This is sent when you've connected.
     pub const IRC_READY: &'static str = "001";
+    /// The host you're connected to -- `002`.
+    pub const YOUR_HOST: &'static str = "002";
+    /// When the server was created -- `003`.
+    pub const CREATED: &'static str = "003";
+    /// Information about the server -- `004`.
+    pub const MY_INFO: &'static str = "004";
+    /// A list of users on a channel -- `353`.
+    pub const NAMES_REPLY: &'static str = "353";
+    /// The end of the list of users on a channel -- `366`.
+    pub const END_OF_NAMES: &'static str = "366";
+    /// A line of the message of the day -- `372`.
+    pub const MOTD: &'static str = "372";
+    /// The start of the message of the day -- `375`.
+    pub const MOTD_START: &'static str = "375";
     /// A Trovo Ready event -- `376`.
     ///
     /// This is sent by Trovo with your user information.
     pub const READY: &'static str = "376";
+    /// The server didn't understand a command you sent -- `421`.
+    pub const UNKNOWN_COMMAND: &'static str = "421";
     /// A capability response -- `CAP`.
     ///
     /// This is sent to acknowledge whether the capability requested is valid and applied to your connections.
@@ -253,6 +288,19 @@ mod tests {
         crate::serde::round_trip_rmp::<IrcMessage>(input);
     }
 
+    #[test]
+    fn nth_arg_index_repeated_spaces() {
+        let input = ":tmi.trovo.tv CAP  *   ACK :trovo.tv/membership\r\n";
+        let msg = IrcMessage::parse(MaybeOwned::Borrowed(input)).unwrap();
+        assert_eq!(msg.get_command(), "CAP");
+        for (i, expected) in ["*", "ACK"].iter().enumerate() {
+            assert_eq!(msg.nth_arg(i).unwrap(), *expected);
+            assert_eq!(&msg.raw[msg.nth_arg_index(i).unwrap()], *expected);
+        }
+        assert!(msg.nth_arg_index(2).is_none());
+        assert_eq!(msg.get_data().unwrap(), "trovo.tv/membership");
+    }
+
     #[test]
     fn parse_empty_spaces() {
         for i in 0..10 {
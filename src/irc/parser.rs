@@ -1,70 +1,288 @@
 use super::{IrcMessage, MessageError, PrefixIndex};
 use crate::{MaybeOwned, MaybeOwnedIndex};
 
+/// How strictly a message should be parsed
+///
+/// Both modes allow repeated spaces between the parts of a message, client-only (`+`) tags and vendor-prefixed tag keys.
+///
+/// ```
+/// # use trovochat::{irc::{self, ParseMode}, MessageError};
+/// // a command has to be letters or a 3-digit numeric
+/// let input = "@foo=bar :tmi.trovo.tv PRIV_MSG #museun :hello\r\n";
+/// assert!(irc::parse_one_with_mode(input, ParseMode::Lenient).is_ok());
+///
+/// let err = irc::parse_one_with_mode(input, ParseMode::Strict).unwrap_err();
+/// assert!(matches!(err, MessageError::InvalidSyntax { pos: 27, .. }));
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ParseMode {
+    /// Accept the quirks seen in the real world.
+    ///
+    /// Leading and trailing whitespace is ignored, and tag keys and commands can be made of any characters.
+    Lenient,
+    /// Follow the IRCv3 message-tags grammar.
+    ///
+    /// Tag keys must be `['+'] [vendor '/'] 1*(letter / digit / '-')`,
+    /// the command must be letters or a 3-digit numeric and `NUL`, `CR` and `LF` are not allowed anywhere.
+    Strict,
+}
+
+pub(super) struct Indices {
+    pub(super) tags: Option<MaybeOwnedIndex>,
+    pub(super) prefix: Option<PrefixIndex>,
+    pub(super) command: MaybeOwnedIndex,
+    pub(super) args: Option<MaybeOwnedIndex>,
+    pub(super) data: Option<MaybeOwnedIndex>,
+}
+
+// NOTE: this works on bytes so it can be used for both `&str` and `&[u8]`.
+// all of the delimiters are ascii, so any index found is also a char boundary
+//
+// all positions are byte offsets into the original input
 pub(super) struct Parser<'a> {
-    pub(super) input: &'a str,
-    pub(super) pos: usize,
+    input: &'a [u8],
+    pos: usize,
+    end: usize,
+    mode: ParseMode,
 }
 
 impl<'a> Parser<'a> {
-    fn mark_index(&mut self, tail: usize, adv: usize) -> MaybeOwnedIndex {
-        let index = MaybeOwnedIndex::raw(self.pos, self.pos + tail);
-        self.pos += adv;
-        index
+    pub(super) fn parse(input: &'a [u8], mode: ParseMode) -> Result<Indices, MessageError> {
+        let mut this = Self {
+            input,
+            pos: 0,
+            end: input.len(),
+            mode,
+        };
+        this.trim()?;
+
+        let tags = this.tags()?;
+        let prefix = this.prefix()?;
+        let command = this.command()?;
+        let (args, data) = this.params()?;
+
+        Ok(Indices {
+            tags,
+            prefix,
+            command,
+            args,
+            data,
+        })
     }
 
-    pub(super) fn tags(&mut self) -> Option<MaybeOwnedIndex> {
-        let input = self.input.get(self.pos..)?;
-        if input.starts_with('@') {
-            if let Some(end) = input.find(' ') {
-                return Some(self.mark_index(end, end + 1));
-            }
+    fn error(pos: usize, expected: &'static str) -> MessageError {
+        MessageError::InvalidSyntax { pos, expected }
+    }
+
+    fn is_strict(&self) -> bool {
+        self.mode == ParseMode::Strict
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input[..self.end].get(self.pos).copied()
+    }
+
+    // find the end of the current token, starting at 'pos'
+    fn token_end(&self) -> usize {
+        self.input[self.pos..self.end]
+            .iter()
+            .position(|&c| c == b' ')
+            .map_or(self.end, |end| self.pos + end)
+    }
+
+    fn skip_spaces(&mut self) {
+        while let Some(b' ') = self.peek() {
+            self.pos += 1;
         }
-        None
     }
 
-    pub(super) fn prefix(&mut self) -> Option<PrefixIndex> {
-        let input = self.input.get(self.pos..)?;
-        if input.starts_with(':') {
-            if let Some(pos) = input.find(' ') {
+    fn expect_space(&mut self, expected: &'static str) -> Result<(), MessageError> {
+        if self.peek() != Some(b' ') {
+            return Err(Self::error(self.pos, expected));
+        }
+        self.skip_spaces();
+        Ok(())
+    }
+
+    fn trim(&mut self) -> Result<(), MessageError> {
+        if self.is_strict() {
+            if self.input.ends_with(b"\r\n") {
+                self.end -= 2;
+            }
+
+            let forbidden = |c: &u8| matches!(c, b'\0' | b'\r' | b'\n');
+            if let Some(pos) = self.input[..self.end].iter().position(forbidden) {
+                return Err(Self::error(
+                    pos,
+                    "no NUL, CR or LF before the end of the line",
+                ));
+            }
+        } else {
+            while let Some(c) = self.input[..self.end].last() {
+                if !c.is_ascii_whitespace() {
+                    break;
+                }
+                self.end -= 1;
+            }
+
+            while let Some(c) = self.peek() {
+                if !c.is_ascii_whitespace() {
+                    break;
+                }
                 self.pos += 1;
-                let prefix = match input.find('!') {
-                    Some(bang) => PrefixIndex::User {
-                        nick: self.mark_index(bang - 1, pos),
-                    },
-                    None => PrefixIndex::Server {
-                        host: self.mark_index(pos - 1, pos),
-                    },
-                };
-
-                return Some(prefix);
             }
         }
-        None
+
+        if self.pos == self.end {
+            return Err(MessageError::EmptyMessage);
+        }
+
+        Ok(())
     }
 
-    pub(super) fn command(&mut self) -> MaybeOwnedIndex {
-        let input = &self.input[self.pos..];
-        let pos = input.find(' ').unwrap_or_else(|| input.len());
-        self.mark_index(pos, pos + 1)
+    fn tags(&mut self) -> Result<Option<MaybeOwnedIndex>, MessageError> {
+        if self.peek() != Some(b'@') {
+            return Ok(None);
+        }
+
+        let start = self.pos;
+        let end = self.token_end();
+
+        let mut pos = start + 1;
+        for tag in self.input[pos..end].split(|&c| c == b';') {
+            if self.is_strict() {
+                Self::validate_tag(tag, pos)?;
+            }
+            pos += tag.len() + 1;
+        }
+
+        self.pos = end;
+        self.expect_space("a space after the tags")?;
+        Ok(Some(MaybeOwnedIndex::raw(start, end)))
     }
 
-    pub(super) fn args(&mut self) -> Option<MaybeOwnedIndex> {
-        if self.pos > self.input.len() || self.input[self.pos..].starts_with(':') {
-            return None;
+    // ['+'] [ vendor '/' ] 1*( letter / digit / '-' ) ['=' value]
+    pub(super) fn validate_tag(tag: &[u8], offset: usize) -> Result<(), MessageError> {
+        const EXPECTED_KEY: &str = "a tag key of letters, digits or '-'";
+
+        let key_len = tag.iter().position(|&c| c == b'=').unwrap_or(tag.len());
+        let key = &tag[..key_len];
+
+        let mut start = 0;
+        if key.starts_with(b"+") {
+            start += 1;
+        }
+
+        if let Some(slash) = key.iter().rposition(|&c| c == b'/') {
+            let vendor = &key[start..slash];
+            if vendor.is_empty() {
+                return Err(Self::error(offset + start, "a vendor before the '/'"));
+            }
+
+            let is_host = |c: &u8| c.is_ascii_alphanumeric() || matches!(c, b'-' | b'.');
+            if let Some(pos) = vendor.iter().position(|c| !is_host(c)) {
+                return Err(Self::error(offset + start + pos, "a vendor hostname"));
+            }
+            start = slash + 1;
+        }
+
+        let name = &key[start..];
+        if name.is_empty() {
+            return Err(Self::error(offset + start, EXPECTED_KEY));
+        }
+
+        let is_key = |c: &u8| c.is_ascii_alphanumeric() || *c == b'-';
+        if let Some(pos) = name.iter().position(|c| !is_key(c)) {
+            return Err(Self::error(offset + start + pos, EXPECTED_KEY));
+        }
+
+        Ok(())
+    }
+
+    fn prefix(&mut self) -> Result<Option<PrefixIndex>, MessageError> {
+        if self.peek() != Some(b':') {
+            return Ok(None);
+        }
+
+        let start = self.pos + 1;
+        let end = self.token_end();
+        if start == end {
+            return Err(Self::error(start, "a prefix"));
         }
 
-        let input = self.input.get(self.pos..)?;
-        let pos = input.find(" :").unwrap_or_else(|| input.len());
-        Some(self.mark_index(pos, pos))
+        let prefix = &self.input[start..end];
+        let prefix = match prefix.iter().position(|&c| c == b'!' || c == b'@') {
+            Some(0) => return Err(Self::error(start, "a nickname")),
+            Some(nick) => PrefixIndex::User {
+                nick: MaybeOwnedIndex::raw(start, start + nick),
+            },
+            None => PrefixIndex::Server {
+                host: MaybeOwnedIndex::raw(start, end),
+            },
+        };
+
+        self.pos = end;
+        self.expect_space("a space after the prefix")?;
+        Ok(Some(prefix))
     }
 
-    pub(super) fn data(self) -> Option<MaybeOwnedIndex> {
-        let pos = self.input.get(self.pos..).and_then(|s| s.find(':'))?;
-        self.input
-            .get(self.pos + pos + 1..)
-            .filter(|s| !s.is_empty())
-            .map(|_| MaybeOwnedIndex::raw(self.pos + pos + 1, self.input.len()))
+    fn command(&mut self) -> Result<MaybeOwnedIndex, MessageError> {
+        const EXPECTED: &str = "a command of letters or a 3-digit numeric";
+
+        let (start, end) = (self.pos, self.token_end());
+        if start == end {
+            return Err(Self::error(start, "a command"));
+        }
+
+        if self.is_strict() {
+            let command = &self.input[start..end];
+            let pos = if matches!(command.first(), Some(c) if c.is_ascii_digit()) {
+                match command.iter().position(|c| !c.is_ascii_digit()) {
+                    Some(pos) => Some(pos),
+                    None if command.len() != 3 => Some(command.len().min(3)),
+                    None => None,
+                }
+            } else {
+                command.iter().position(|c| !c.is_ascii_alphabetic())
+            };
+
+            if let Some(pos) = pos {
+                return Err(Self::error(start + pos, EXPECTED));
+            }
+        }
+
+        self.pos = end;
+        Ok(MaybeOwnedIndex::raw(start, end))
+    }
+
+    fn params(
+        &mut self,
+    ) -> Result<(Option<MaybeOwnedIndex>, Option<MaybeOwnedIndex>), MessageError> {
+        let mut args: Option<MaybeOwnedIndex> = None;
+
+        while self.pos < self.end {
+            self.expect_space("a space before the parameter")?;
+
+            match self.peek() {
+                // trailing spaces
+                None => break,
+                Some(b':') => {
+                    let data = Some(MaybeOwnedIndex::raw(self.pos + 1, self.end))
+                        .filter(|index| !index.is_empty());
+                    return Ok((args, data));
+                }
+                Some(..) => {
+                    let (start, end) = (self.pos, self.token_end());
+                    args = Some(match args {
+                        Some(args) => MaybeOwnedIndex::raw(args.start as _, end),
+                        None => MaybeOwnedIndex::raw(start, end),
+                    });
+                    self.pos = end;
+                }
+            }
+        }
+
+        Ok((args, None))
     }
 }
 
@@ -105,3 +323,89 @@ impl<'a> Iterator for IrcParserIter<'a> {
             .map(IrcMessage::parse)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str, mode: ParseMode) -> Result<Indices, MessageError> {
+        Parser::parse(input.as_bytes(), mode)
+    }
+
+    fn expect_error(input: &str, mode: ParseMode, expected_pos: usize) {
+        match parse(input, mode) {
+            Err(MessageError::InvalidSyntax { pos, .. }) => {
+                assert_eq!(pos, expected_pos, "{:?}", input)
+            }
+            Err(err) => panic!("unexpected error for {:?}: {}", input, err),
+            Ok(..) => panic!("expected an error for {:?}", input),
+        }
+    }
+
+    #[test]
+    fn repeated_spaces() {
+        let input = "@a=b   :nick!user@host   PRIVMSG   #museun   :hello  world\r\n";
+        for &mode in &[ParseMode::Lenient, ParseMode::Strict] {
+            let msg = parse(input, mode).unwrap();
+            assert_eq!(&input[msg.tags.unwrap()], "@a=b");
+            assert_eq!(&input[msg.prefix.unwrap().as_index()], "nick");
+            assert_eq!(&input[msg.command], "PRIVMSG");
+            assert_eq!(&input[msg.args.unwrap()], "#museun");
+            assert_eq!(&input[msg.data.unwrap()], "hello  world");
+        }
+    }
+
+    #[test]
+    fn client_only_and_vendor_tags() {
+        let input = "@+example.com/foo=bar;+baz;example.com/quux=;id=1 PRIVMSG #museun :hi\r\n";
+        for &mode in &[ParseMode::Lenient, ParseMode::Strict] {
+            let msg = parse(input, mode).unwrap();
+            assert_eq!(
+                &input[msg.tags.unwrap()],
+                "@+example.com/foo=bar;+baz;example.com/quux=;id=1"
+            );
+        }
+    }
+
+    #[test]
+    fn colon_in_middle_param() {
+        let input = ":tmi.trovo.tv PRIVMSG #a:b :hello\r\n";
+        let msg = parse(input, ParseMode::Strict).unwrap();
+        assert!(msg.prefix.unwrap().is_server());
+        assert_eq!(&input[msg.args.unwrap()], "#a:b");
+        assert_eq!(&input[msg.data.unwrap()], "hello");
+    }
+
+    #[test]
+    fn strict_errors() {
+        let strict = ParseMode::Strict;
+        expect_error("@a_b=c PING\r\n", strict, 2);
+        expect_error("@=c PING\r\n", strict, 1);
+        expect_error("@a=b;+/c=d PING\r\n", strict, 6);
+        expect_error("@a=b\r\n", strict, 4);
+        expect_error(": PING\r\n", strict, 1);
+        expect_error(":nick PRIV_MSG\r\n", strict, 10);
+        expect_error("12 #museun\r\n", strict, 2);
+        expect_error("1234 #museun\r\n", strict, 3);
+        expect_error(" PING\r\n", strict, 0);
+        expect_error("PING :a\rb\r\n", strict, 7);
+    }
+
+    #[test]
+    fn lenient_quirks() {
+        let lenient = ParseMode::Lenient;
+        for input in &[
+            "@a_b=c PING\r\n",
+            ":nick PRIV_MSG\r\n",
+            "12 #museun\r\n",
+            "   PING :foo   \r\n",
+        ] {
+            parse(input, lenient).unwrap();
+        }
+
+        // these are still errors
+        expect_error("@a=b\r\n", lenient, 4);
+        expect_error(": PING\r\n", lenient, 1);
+        expect_error(":nick\r\n", lenient, 5);
+    }
+}
@@ -0,0 +1,304 @@
+use super::{parser::Parser, IrcMessage, IrcMessageBuilderError};
+use crate::{Encodable, MaybeOwned};
+use std::io::{Error as IoError, ErrorKind, Result as IoResult, Write};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Rewrite<'a> {
+    Keep,
+    Strip,
+    Replace(&'a str),
+}
+
+/**
+Re-encodes a parsed message in its canonical wire form, optionally rewriting its tags or prefix.
+
+The canonical form is `[@tags ][:prefix ]COMMAND[ arg]*[ :data]\r\n`, with single spaces between each part.
+
+This is produced by the `reencode()` method on [IrcMessage], [Commands](crate::messages::Commands) and all of the types in [messages](crate::messages).
+
+```
+# use trovochat::{irc, Encodable as _};
+let input = "@id=123  :museun!museun@museun.tmi.trovo.tv  PRIVMSG  #museun :hello world\r\n";
+let (_, msg) = irc::parse_one(input).unwrap();
+
+let mut out = vec![];
+msg.encode(&mut out).unwrap();
+assert_eq!(
+    std::str::from_utf8(&out).unwrap(),
+    "@id=123 :museun!museun@museun.tmi.trovo.tv PRIVMSG #museun :hello world\r\n"
+);
+
+let mut out = vec![];
+msg.reencode().strip_tags().prefix("tmi.trovo.tv").encode(&mut out).unwrap();
+assert_eq!(
+    std::str::from_utf8(&out).unwrap(),
+    ":tmi.trovo.tv PRIVMSG #museun :hello world\r\n"
+);
+```
+*/
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Reencode<'a> {
+    raw: &'a str,
+    tags: Rewrite<'a>,
+    prefix: Rewrite<'a>,
+}
+
+impl<'a> Reencode<'a> {
+    /// Re-encode this raw message
+    pub fn new(raw: &'a str) -> Self {
+        Self {
+            raw,
+            tags: Rewrite::Keep,
+            prefix: Rewrite::Keep,
+        }
+    }
+
+    /// Don't write any tags
+    pub fn strip_tags(mut self) -> Self {
+        self.tags = Rewrite::Strip;
+        self
+    }
+
+    /// Replace the tags with these already-escaped tags, e.g. `key1=val1;key2=val2`
+    ///
+    /// Encoding fails if these contain a space, `CR`, `LF` or `NUL`, or an invalid key.
+    pub fn tags(mut self, tags: &'a str) -> Self {
+        self.tags = Rewrite::Replace(tags.trim_start_matches('@'));
+        self
+    }
+
+    /// Don't write a prefix
+    pub fn strip_prefix(mut self) -> Self {
+        self.prefix = Rewrite::Strip;
+        self
+    }
+
+    /// Replace the prefix with this one, e.g. `tmi.trovo.tv`
+    ///
+    /// Encoding fails if this is empty or contains a space, `CR`, `LF` or `NUL`.
+    pub fn prefix(mut self, prefix: &'a str) -> Self {
+        self.prefix = Rewrite::Replace(prefix.trim_start_matches(':'));
+        self
+    }
+
+    // the replacements are written as-is, so check them the same way the IrcMessageBuilder does
+    fn validate(&self) -> Result<(), IrcMessageBuilderError> {
+        type Error = IrcMessageBuilderError;
+
+        let is_forbidden = |c: char| matches!(c, ' ' | '\0' | '\r' | '\n');
+
+        if let Rewrite::Replace(tags) = self.tags {
+            if tags.contains(is_forbidden) {
+                return Err(Error::InvalidTags {
+                    tags: tags.to_string(),
+                });
+            }
+            if let Some(tag) = tags
+                .split(';')
+                .filter(|tag| !tag.is_empty())
+                .find(|tag| Parser::validate_tag(tag.as_bytes(), 0).is_err())
+            {
+                let key = tag.split('=').next().unwrap_or(tag);
+                return Err(Error::InvalidTagKey {
+                    key: key.to_string(),
+                });
+            }
+        }
+
+        if let Rewrite::Replace(prefix) = self.prefix {
+            if prefix.is_empty() || prefix.contains(is_forbidden) {
+                return Err(Error::InvalidPrefix {
+                    prefix: prefix.to_string(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> Encodable for Reencode<'a> {
+    fn encode<W>(&self, buf: &mut W) -> IoResult<()>
+    where
+        W: Write + ?Sized,
+    {
+        self.validate()
+            .map_err(|err| IoError::new(ErrorKind::InvalidInput, err))?;
+        let msg = IrcMessage::parse(MaybeOwned::Borrowed(self.raw))
+            .map_err(|err| IoError::new(ErrorKind::InvalidData, err))?;
+        write_canonical(&msg, self.tags, self.prefix, buf)
+    }
+}
+
+impl<'a> Encodable for IrcMessage<'a> {
+    fn encode<W>(&self, buf: &mut W) -> IoResult<()>
+    where
+        W: Write + ?Sized,
+    {
+        write_canonical(self, Rewrite::Keep, Rewrite::Keep, buf)
+    }
+}
+
+fn write_canonical<W>(
+    msg: &IrcMessage<'_>,
+    tags: Rewrite<'_>,
+    prefix: Rewrite<'_>,
+    buf: &mut W,
+) -> IoResult<()>
+where
+    W: Write + ?Sized,
+{
+    let tags = match tags {
+        Rewrite::Keep => msg.get_tags().map(|s| s.trim_start_matches('@')),
+        Rewrite::Replace(tags) => Some(tags),
+        Rewrite::Strip => None,
+    };
+    if let Some(tags) = tags.filter(|s| !s.is_empty()) {
+        write!(buf, "@{} ", tags)?;
+    }
+
+    let prefix = match prefix {
+        Rewrite::Keep => full_prefix(msg),
+        Rewrite::Replace(prefix) => Some(prefix),
+        Rewrite::Strip => None,
+    };
+    if let Some(prefix) = prefix.filter(|s| !s.is_empty()) {
+        write!(buf, ":{} ", prefix)?;
+    }
+
+    buf.write_all(msg.get_command().as_bytes())?;
+
+    if let Some(args) = msg.get_args() {
+        for arg in args.split_ascii_whitespace() {
+            write!(buf, " {}", arg)?;
+        }
+    }
+
+    if let Some(data) = msg.get_data() {
+        write!(buf, " :{}", data)?;
+    } else if has_empty_trailing(msg) {
+        buf.write_all(b" :")?;
+    }
+
+    buf.write_all(b"\r\n")
+}
+
+// the prefix index only covers the nickname (or host), so find the end of the whole prefix
+fn full_prefix<'a>(msg: &'a IrcMessage<'_>) -> Option<&'a str> {
+    let start = msg.prefix?.as_index().start as usize;
+    let tail = &msg.get_raw()[start..];
+    let end = tail.find(' ').unwrap_or(tail.len());
+    Some(&tail[..end])
+}
+
+// the parser doesn't index empty data, so check whether there was a ':' after the last argument
+fn has_empty_trailing(msg: &IrcMessage<'_>) -> bool {
+    let end = msg.args.unwrap_or(msg.command).end as usize;
+    msg.get_raw()[end..]
+        .trim_start_matches(' ')
+        .starts_with(':')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{messages::Commands, FromIrcMessage as _};
+
+    fn encode(msg: impl Encodable) -> String {
+        let mut out = vec![];
+        msg.encode(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn canonical() {
+        let tests = &[
+            (
+                "@a=b;c= :museun!museun@museun.tmi.trovo.tv PRIVMSG #museun :hello world\r\n",
+                "@a=b;c= :museun!museun@museun.tmi.trovo.tv PRIVMSG #museun :hello world\r\n",
+            ),
+            (
+                "  :tmi.trovo.tv   CAP  *  ACK   :trovo.tv/tags  \r\n",
+                ":tmi.trovo.tv CAP * ACK :trovo.tv/tags\r\n",
+            ),
+            ("PING :1234\r\n", "PING :1234\r\n"),
+            ("RECONNECT", "RECONNECT\r\n"),
+        ];
+
+        for (input, expected) in tests {
+            let (_, msg) = crate::irc::parse_one(&format!("{}\r\n", input.trim_end()))
+                .map(|(i, msg)| (i, crate::IntoOwned::into_owned(msg)))
+                .unwrap();
+            assert_eq!(encode(&msg), *expected);
+            assert_eq!(encode(msg.reencode()), *expected);
+
+            let all = Commands::from_irc(msg).unwrap();
+            assert_eq!(encode(&all), *expected);
+        }
+    }
+
+    #[test]
+    fn empty_trailing() {
+        let tests = &[
+            ("PRIVMSG #museun :\r\n", "PRIVMSG #museun :\r\n"),
+            ("PRIVMSG  #museun  :\r\n", "PRIVMSG #museun :\r\n"),
+            ("PRIVMSG #museun :  \r\n", "PRIVMSG #museun :\r\n"),
+            ("PRIVMSG #museun\r\n", "PRIVMSG #museun\r\n"),
+            ("PING :\r\n", "PING :\r\n"),
+        ];
+
+        for (input, expected) in tests {
+            let msg = crate::irc::parse_one(input).unwrap().1;
+            assert_eq!(encode(&msg), *expected);
+            assert_eq!(encode(msg.reencode()), *expected);
+        }
+    }
+
+    #[test]
+    fn rewrite() {
+        let input = "@a=b :museun!museun@museun.tmi.trovo.tv PRIVMSG #museun :hello\r\n";
+        let msg =
+            crate::messages::Privmsg::from_irc(crate::irc::parse_one(input).unwrap().1).unwrap();
+
+        assert_eq!(encode(&msg), input);
+        assert_eq!(
+            encode(msg.reencode().strip_tags()),
+            ":museun!museun@museun.tmi.trovo.tv PRIVMSG #museun :hello\r\n"
+        );
+        assert_eq!(
+            encode(msg.reencode().strip_prefix()),
+            "@a=b PRIVMSG #museun :hello\r\n"
+        );
+        assert_eq!(
+            encode(msg.reencode().tags("@c=d;e=f").prefix("tmi.trovo.tv")),
+            "@c=d;e=f :tmi.trovo.tv PRIVMSG #museun :hello\r\n"
+        );
+        assert_eq!(
+            encode(msg.reencode().strip_tags().strip_prefix()),
+            "PRIVMSG #museun :hello\r\n"
+        );
+    }
+
+    #[test]
+    fn rewrite_rejects_injection() {
+        let input = "PRIVMSG #museun :hello\r\n";
+        let msg = crate::irc::parse_one(input).unwrap().1;
+
+        let tests = &[
+            msg.reencode().tags("a=b\r\nQUIT"),
+            msg.reencode().tags("a=b c"),
+            msg.reencode().tags("a=b;c d=e"),
+            msg.reencode().tags("a_b=c"),
+            msg.reencode().prefix("tmi.trovo.tv\r\nQUIT"),
+            msg.reencode().prefix("tmi.trovo.tv PART #museun"),
+            msg.reencode().prefix(""),
+        ];
+
+        for test in tests {
+            let mut out = vec![];
+            let err = test.encode(&mut out).unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+            assert!(out.is_empty());
+        }
+    }
+}
@@ -0,0 +1,257 @@
+use super::{
+    parser::Parser,
+    tags::{escape_str, unescape_str},
+    Tags,
+};
+use crate::Encodable;
+use std::{
+    borrow::Borrow,
+    io::{Result as IoResult, Write},
+};
+
+/**
+An owned, mutable set of IRCv3 message tags
+
+Values are stored unescaped, and are escaped with [escape_str](super::escape_str) when encoded.
+
+The insertion order of the keys is kept.
+
+```
+# use trovochat::irc::TagMap;
+let mut tags = TagMap::new();
+tags.insert("+example.com/foo", "hello world");
+tags.insert("+reply-parent-msg-id", "aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa");
+
+assert_eq!(tags.get("+example.com/foo"), Some("hello world"));
+assert_eq!(
+    tags.to_string(),
+    "+example.com/foo=hello\\sworld;+reply-parent-msg-id=aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa"
+);
+
+assert_eq!(tags.remove("+example.com/foo").as_deref(), Some("hello world"));
+assert_eq!(tags.len(), 1);
+```
+*/
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct TagMap {
+    map: Vec<(String, String)>,
+}
+
+impl TagMap {
+    /// Create a new, empty tag map
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert this tag with an unescaped value, returning the previous value if this key already existed
+    ///
+    /// The key isn't validated here, an invalid key will produce an error when it is encoded.
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<String>) -> Option<String> {
+        let (key, value) = (key.into(), value.into());
+        match self.map.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, old)) => Some(std::mem::replace(old, value)),
+            None => {
+                self.map.push((key, value));
+                None
+            }
+        }
+    }
+
+    /// Remove this tag, returning its unescaped value if it existed
+    pub fn remove<K>(&mut self, key: &K) -> Option<String>
+    where
+        K: ?Sized + Borrow<str>,
+    {
+        let pos = self.map.iter().position(|(k, _)| k == key.borrow())?;
+        Some(self.map.remove(pos).1)
+    }
+
+    /// Merge all of the tags from `other` into this map, replacing any existing values
+    pub fn merge(&mut self, other: impl IntoIterator<Item = (String, String)>) {
+        for (key, value) in other {
+            self.insert(key, value);
+        }
+    }
+
+    /// Get the unescaped value for this key
+    pub fn get<K>(&self, key: &K) -> Option<&str>
+    where
+        K: ?Sized + Borrow<str>,
+    {
+        self.map
+            .iter()
+            .find(|(k, _)| k == key.borrow())
+            .map(|(_, v)| &**v)
+    }
+
+    /// Determines whether this key exists
+    pub fn contains_key<K>(&self, key: &K) -> bool
+    where
+        K: ?Sized + Borrow<str>,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Returns how many tags there are
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns whether there are any tags
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Get an iterator over the `key, unescaped value` pairs of tags
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> + '_ {
+        self.map.iter().map(|(k, v)| (&**k, &**v))
+    }
+
+    /// Parse a tags fragment, such as `@k1=v1;k2=v2` or `k1=v1;k2=v2`, unescaping the values
+    pub fn parse(input: &str) -> Self {
+        input
+            .trim_start_matches('@')
+            .split(';')
+            .filter(|s| !s.is_empty())
+            .map(|tag| {
+                let mut iter = tag.splitn(2, '=');
+                let key = iter.next().unwrap_or_default();
+                let value = iter.next().unwrap_or_default();
+                (key.to_string(), unescape_str(value).to_string())
+            })
+            .collect()
+    }
+}
+
+impl std::fmt::Display for TagMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, (key, value)) in self.map.iter().enumerate() {
+            if i > 0 {
+                f.write_str(";")?;
+            }
+            write!(f, "{}={}", key, escape_str(value))?;
+        }
+        Ok(())
+    }
+}
+
+/// Encodes the tags as `@k1=v1;k2=v2 `, or nothing if there are no tags
+impl Encodable for TagMap {
+    fn encode<W>(&self, buf: &mut W) -> IoResult<()>
+    where
+        W: Write + ?Sized,
+    {
+        if self.is_empty() {
+            return Ok(());
+        }
+
+        if let Some((key, _)) = self
+            .map
+            .iter()
+            .find(|(key, _)| Parser::validate_tag(key.as_bytes(), 0).is_err())
+        {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("invalid tag key: '{}'", key),
+            ));
+        }
+
+        write!(buf, "@{} ", self)
+    }
+}
+
+impl std::iter::FromIterator<(String, String)> for TagMap {
+    fn from_iter<I: IntoIterator<Item = (String, String)>>(iter: I) -> Self {
+        let mut this = Self::new();
+        this.merge(iter);
+        this
+    }
+}
+
+impl<'a> std::iter::FromIterator<(&'a str, &'a str)> for TagMap {
+    fn from_iter<I: IntoIterator<Item = (&'a str, &'a str)>>(iter: I) -> Self {
+        iter.into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+}
+
+impl IntoIterator for TagMap {
+    type Item = (String, String);
+    type IntoIter = std::vec::IntoIter<(String, String)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.map.into_iter()
+    }
+}
+
+impl<'a> From<&Tags<'a>> for TagMap {
+    fn from(tags: &Tags<'a>) -> Self {
+        tags.iter()
+            .map(|(k, v)| (k.to_string(), unescape_str(v).to_string()))
+            .collect()
+    }
+}
+
+impl<'a> From<Tags<'a>> for TagMap {
+    fn from(tags: Tags<'a>) -> Self {
+        Self::from(&tags)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{messages::Privmsg, FromIrcMessage as _};
+
+    #[test]
+    fn insert_remove_merge() {
+        let mut tags = TagMap::new();
+        assert!(tags.insert("a", "1").is_none());
+        assert!(tags.insert("b", "2").is_none());
+        assert_eq!(tags.insert("a", "3").as_deref(), Some("1"));
+        assert_eq!(tags.to_string(), "a=3;b=2");
+
+        tags.merge(TagMap::parse("@b=4;c=a\\sb"));
+        assert_eq!(tags.get("b"), Some("4"));
+        assert_eq!(tags.get("c"), Some("a b"));
+        assert_eq!(tags.to_string(), "a=3;b=4;c=a\\sb");
+
+        assert_eq!(tags.remove("a").as_deref(), Some("3"));
+        assert!(tags.remove("a").is_none());
+        assert!(!tags.contains_key("a"));
+        assert_eq!(tags.len(), 2);
+    }
+
+    #[test]
+    fn encode() {
+        let mut out = vec![];
+        TagMap::new().encode(&mut out).unwrap();
+        assert!(out.is_empty());
+
+        let tags: TagMap = vec![("+foo", "a;b"), ("bar", "")].into_iter().collect();
+        tags.encode(&mut out).unwrap();
+        assert_eq!(std::str::from_utf8(&out).unwrap(), "@+foo=a\\:b;bar= ");
+
+        let tags: TagMap = vec![("bad key", "")].into_iter().collect();
+        assert!(tags.encode(&mut vec![]).is_err());
+    }
+
+    #[test]
+    fn from_tags() {
+        let input = "@badge-info=;display-name=Museun;msg=a\\sb :museun!museun@museun.tmi.trovo.tv PRIVMSG #museun :hello\r\n";
+        let msg = Privmsg::from_irc(crate::irc::parse_one(input).unwrap().1).unwrap();
+
+        let mut tags = TagMap::from(msg.tags());
+        assert_eq!(tags.get("badge-info"), Some(""));
+        assert_eq!(tags.get("display-name"), Some("Museun"));
+        assert_eq!(tags.get("msg"), Some("a b"));
+
+        tags.insert("+example.com/foo", "bar");
+        assert_eq!(
+            tags.to_string(),
+            "badge-info=;display-name=Museun;msg=a\\sb;+example.com/foo=bar"
+        );
+    }
+}
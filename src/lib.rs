@@ -40,7 +40,7 @@ To use the [AsyncRunner] (an async-event loop) and related helpers, you must abl
 ***NOTE*** This is a breaking change from `0.12` which had the async stuff enabled by default.
 
 ```toml
-trovochat = { version = "0.14", features = ["async"] }
+trovochat = { version = "0.15", features = ["async"] }
 ```
 ---
 
@@ -122,6 +122,15 @@ cfg_async! { pub use runner::AsyncRunner; }
 
 pub mod rate_limit;
 
+pub mod gift_bomb;
+
+#[cfg(any(feature = "tokio-util", feature = "asynchronous-codec"))]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(any(feature = "tokio-util", feature = "asynchronous-codec")))
+)]
+pub mod codec;
+
 pub mod commands;
 pub mod messages;
 
@@ -134,6 +143,78 @@ pub mod test;
 #[doc(inline)]
 pub use irc::{FromIrcMessage, IntoIrcMessage};
 
+/**
+Derive [FromIrcMessage], [IntoOwned] and accessors for your own message types
+
+The type must have a single lifetime and a `raw: MaybeOwned<'a>` field. A `tags: TagIndices` field is optional.
+
+Every other field needs an attribute saying where it comes from:
+
+| attribute                | field type                                  | accessor                         |
+| ------------------------ | ------------------------------------------- | -------------------------------- |
+| `#[irc(nick)]`           | `MaybeOwnedIndex` or `Option<..>` of it     | `&str` or `Option<&str>`         |
+| `#[irc(arg = 0)]`        | `MaybeOwnedIndex` or `Option<..>` of it     | `&str` or `Option<&str>`         |
+| `#[irc(data)]`           | `MaybeOwnedIndex` or `Option<..>` of it     | `&str` or `Option<&str>`         |
+| `#[irc(tag = "user-id")]`| any owned `T: FromStr`, or `Option<T>`      | a clone of the value             |
+
+A missing non-`Option` field produces the same [MessageError] that the hand-written types produce.
+
+The command is set with `#[irc(command = "...")]` on the type.
+
+`raw()`, `reencode()` and (if it has tags) `tags()` are also generated, as are `serde` impls when the `serde` feature is enabled.
+
+```
+use trovochat::{irc::TagIndices, maybe_owned::{MaybeOwned, MaybeOwnedIndex}};
+use trovochat::{FromIrcMessage, IntoOwned as _};
+
+#[derive(Clone, PartialEq, FromIrcMessage)]
+#[irc(command = "VIP")]
+struct Vip<'a> {
+    raw: MaybeOwned<'a>,
+    tags: TagIndices,
+    /// The channel this happened on
+    #[irc(arg = 0)]
+    channel: MaybeOwnedIndex,
+    /// The user that was given VIP, if any
+    #[irc(data)]
+    user: Option<MaybeOwnedIndex>,
+    /// The id of the user
+    #[irc(tag = "target-user-id")]
+    user_id: u64,
+}
+
+let input = "@target-user-id=23196011 :tmi.trovo.tv VIP #museun :shaken_bot\r\n";
+let msg = trovochat::irc::parse_one(input).unwrap().1;
+let vip = Vip::from_irc(msg).unwrap().into_owned();
+
+assert_eq!(vip.channel(), "#museun");
+assert_eq!(vip.user(), Some("shaken_bot"));
+assert_eq!(vip.user_id(), 23196011);
+assert_eq!(vip.tags().get("target-user-id"), Some("23196011"));
+
+let msg = trovochat::irc::parse_one(":tmi.trovo.tv VIP #museun\r\n").unwrap().1;
+assert!(matches!(
+    Vip::from_irc(msg),
+    Err(trovochat::MessageError::ExpectedTag { .. })
+));
+```
+*/
+#[cfg(feature = "derive")]
+#[cfg_attr(docsrs, doc(cfg(feature = "derive")))]
+pub use trovochat_derive::FromIrcMessage;
+
+#[cfg(feature = "derive")]
+#[doc(hidden)]
+pub mod derive;
+
+// so the derive can be tested in this crate
+#[cfg(all(test, feature = "derive"))]
+extern crate self as trovochat;
+
+// the dependency is renamed in Cargo.toml so the 'tokio-util' feature can also enable 'bytes'
+#[cfg(feature = "tokio-util")]
+extern crate tokio_util_crate as tokio_util;
+
 pub mod trovo;
 pub use trovo::UserConfig;
 
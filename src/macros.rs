@@ -63,6 +63,13 @@ macro_rules! raw {
         pub fn raw(&self) -> &str {
             &*self.raw
         }
+
+        /// Re-encode this message in its canonical form, optionally rewriting its tags or prefix
+        ///
+        /// See [Reencode](crate::irc::Reencode)
+        pub fn reencode(&self) -> crate::irc::Reencode<'_> {
+            crate::irc::Reencode::new(&*self.raw)
+        }
     };
 }
 
@@ -13,8 +13,9 @@ impl<'a> IntoOwned<'a> for MaybeOwned<'a> {
     type Output = MaybeOwned<'static>;
     fn into_owned(self) -> Self::Output {
         match self {
-            Self::Owned(s) => MaybeOwned::Owned(s),
-            Self::Borrowed(s) => MaybeOwned::Owned(s.to_string().into_boxed_str()),
+            Self::Owned(s) => MaybeOwned::Shared(s.into()),
+            Self::Borrowed(s) => MaybeOwned::Shared(s.into()),
+            Self::Shared(s) => MaybeOwned::Shared(s),
         }
     }
 }
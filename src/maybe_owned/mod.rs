@@ -3,7 +3,12 @@
 //! It is read-only unlike the std implementation.
 //!
 //! Its also specialized for just `str`
-use std::{fmt::Debug, ops::Deref};
+use std::{
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    ops::Deref,
+    sync::Arc,
+};
 
 mod into_owned;
 pub use into_owned::IntoOwned;
@@ -18,23 +23,45 @@ pub use maybe_owned_index::MaybeOwnedIndex;
 /// This crate uses indices into this type to reduce the number of allocations of each type
 ///
 /// This only exposed for people to extend messages themselves.
-#[cfg_attr(feature = "serde", derive(::serde::Serialize), serde(untagged))]
+///
+/// [IntoOwned] produces the `Shared` variant, so cloning an owned message (e.g. a `Commands<'static>`) only bumps a reference count.
+///
+/// More variants may be added in the future, so matches on this need a wildcard arm.
+#[non_exhaustive]
 pub enum MaybeOwned<'a> {
     /// Owned variant, a `Box<str>`. This usually means it has a `'static` lifetime
     Owned(Box<str>),
     /// Borrowed variant, a `&'a str`. This means it has a `'a` lifetime
     Borrowed(&'a str),
+    /// Shared variant, an `Arc<str>`. This has a `'static` lifetime and is cheap to clone
+    Shared(Arc<str>),
 }
 
 impl<'a> MaybeOwned<'a> {
-    /// Checks whether this type is in the `Owned` state
+    /// Checks whether this type is in the `Owned` (or `Shared`) state
     pub fn is_owned(&self) -> bool {
         !self.is_borrowed()
     }
 
     /// Checks whether this type is in the `Borrowed` state
     pub fn is_borrowed(&self) -> bool {
-        matches!(self, Self::Borrowed{..})
+        matches!(self, Self::Borrowed { .. })
+    }
+
+    /// Checks whether this type is in the `Shared` state
+    pub fn is_shared(&self) -> bool {
+        matches!(self, Self::Shared { .. })
+    }
+
+    /// Converts this into the `Shared` state
+    ///
+    /// This copies the string, unless it is already shared.
+    pub fn into_shared(self) -> MaybeOwned<'static> {
+        match self {
+            Self::Shared(s) => MaybeOwned::Shared(s),
+            Self::Owned(s) => MaybeOwned::Shared(s.into()),
+            Self::Borrowed(s) => MaybeOwned::Shared(s.into()),
+        }
     }
 }
 
@@ -43,6 +70,7 @@ impl<'a> Clone for MaybeOwned<'a> {
         match self {
             Self::Owned(s) => Self::Owned(s.to_string().into_boxed_str()),
             Self::Borrowed(s) => Self::Borrowed(s),
+            Self::Shared(s) => Self::Shared(Arc::clone(s)),
         }
     }
 }
@@ -71,11 +99,18 @@ impl<'a> PartialEq<&str> for MaybeOwned<'a> {
     }
 }
 
+impl<'a> Hash for MaybeOwned<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_ref().hash(state)
+    }
+}
+
 impl<'a> AsRef<str> for MaybeOwned<'a> {
     fn as_ref(&self) -> &str {
         match self {
             MaybeOwned::Owned(s) => &*s,
             MaybeOwned::Borrowed(s) => s,
+            MaybeOwned::Shared(s) => s,
         }
     }
 }
@@ -104,3 +139,19 @@ impl From<Box<str>> for MaybeOwned<'static> {
         MaybeOwned::Owned(data)
     }
 }
+
+impl From<Arc<str>> for MaybeOwned<'static> {
+    fn from(data: Arc<str>) -> Self {
+        MaybeOwned::Shared(data)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'a> ::serde::Serialize for MaybeOwned<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ::serde::Serializer,
+    {
+        serializer.serialize_str(self)
+    }
+}
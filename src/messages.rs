@@ -33,6 +33,9 @@
 mod commands;
 pub use commands::Commands;
 
+mod custom;
+pub use custom::{AsCustomMessage, CustomMessage, Registry};
+
 mod irc_ready;
 pub use irc_ready::IrcReady;
 
@@ -43,10 +46,25 @@ mod cap;
 pub use cap::{Cap, Capability};
 
 mod clear_chat;
-pub use clear_chat::ClearChat;
+pub use clear_chat::{ClearAction, ClearChat};
 
 mod clear_msg;
-pub use clear_msg::ClearMsg;
+pub use clear_msg::{ClearMsg, DeletedMessage};
+
+mod accessors;
+pub use accessors::{HasBadges, HasChannel, HasSender, HasTags};
+
+mod timestamped;
+pub use timestamped::{Received, Timestamped};
+
+#[cfg(feature = "serde")]
+mod structured;
+#[cfg(feature = "serde")]
+pub use structured::{Structured, StructuredMessage, STRUCTURED_VERSION};
+
+mod moderation;
+pub use moderation::ModerationEvent;
+cfg_async! { pub use moderation::moderation_events; }
 
 mod global_user_state;
 pub use global_user_state::GlobalUserState;
@@ -69,8 +87,11 @@ pub use ping::Ping;
 mod pong;
 pub use pong::Pong;
 
+mod fragment;
+pub use fragment::Fragment;
+
 mod privmsg;
-pub use privmsg::Privmsg;
+pub use privmsg::{Privmsg, ReplyParent};
 
 mod reconnect;
 pub use reconnect::Reconnect;
@@ -81,10 +102,68 @@ pub use room_state::{FollowersOnly, RoomState};
 mod user_notice;
 pub use user_notice::{NoticeType, SubPlan, UserNotice};
 
+mod user_notice_event;
+pub use user_notice_event::{
+    BitsBadgeTier, GiftPaidUpgrade, Raid, Ritual, SubGift, SubMysteryGift, Subscription,
+    UserNoticeEvent,
+};
+
 mod user_state;
 pub use user_state::UserState;
 
 mod whisper;
 pub use whisper::Whisper;
 
+mod welcome;
+pub use welcome::{Welcome, WelcomeKind};
+
+mod motd;
+pub use motd::Motd;
+
+mod names;
+pub use names::{EndOfNames, Names};
+
+mod unknown_command;
+pub use unknown_command::UnknownCommand;
+
 pub use crate::irc::IrcMessage;
+
+macro_rules! encodable_reencode {
+    ($($ty:ident)*) => {
+        $(impl<'a> crate::Encodable for $ty<'a> {
+            fn encode<W>(&self, buf: &mut W) -> std::io::Result<()>
+            where
+                W: std::io::Write + ?Sized,
+            {
+                crate::Encodable::encode(&self.reencode(), buf)
+            }
+        })*
+    };
+}
+
+encodable_reencode! {
+    Commands
+    IrcReady
+    Ready
+    Cap
+    ClearChat
+    ClearMsg
+    GlobalUserState
+    HostTarget
+    Join
+    Notice
+    Part
+    Ping
+    Pong
+    Privmsg
+    Reconnect
+    RoomState
+    UserNotice
+    UserState
+    Whisper
+    Welcome
+    Motd
+    Names
+    EndOfNames
+    UnknownCommand
+}
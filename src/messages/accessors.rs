@@ -0,0 +1,385 @@
+use super::*;
+use crate::irc::Tags;
+use crate::trovo::{parse_badges, parse_badges_iter, Badge, BadgeInfo, BadgeKind, Color};
+
+/**
+A message that happened on a channel
+
+```
+# use trovochat::{messages::{HasChannel, Join, Privmsg}, FromIrcMessage as _};
+fn channel_of(msg: &dyn HasChannel) -> &str {
+    msg.channel()
+}
+
+let join = ":museun!museun@museun.tmi.trovo.tv JOIN #museun\r\n";
+let join = Join::from_irc(trovochat::irc::parse_one(join).unwrap().1).unwrap();
+assert_eq!(channel_of(&join), "#museun");
+
+let pm = ":museun!museun@museun.tmi.trovo.tv PRIVMSG #shaken_bot :hello\r\n";
+let pm = Privmsg::from_irc(trovochat::irc::parse_one(pm).unwrap().1).unwrap();
+assert_eq!(channel_of(&pm), "#shaken_bot");
+```
+*/
+pub trait HasChannel {
+    /// The channel this message happened on
+    fn channel(&self) -> &str;
+}
+
+/**
+A message that was sent by (or is about) a user
+
+Everything other than the login requires the `Tags` capability.
+
+For a [UserState] or a [GlobalUserState] this describes your own user.
+
+```
+# use trovochat::{messages::{HasSender, Privmsg}, FromIrcMessage as _};
+let input = "@color=#FF0000;display-name=Museun;user-id=23196011 :museun!museun@museun.tmi.trovo.tv PRIVMSG #museun :hello\r\n";
+let pm = Privmsg::from_irc(trovochat::irc::parse_one(input).unwrap().1).unwrap();
+
+let sender: &dyn HasSender = &pm;
+assert_eq!(sender.login(), Some("museun"));
+assert_eq!(sender.display_name(), Some("Museun"));
+assert_eq!(sender.user_id(), Some(23196011));
+assert_eq!(sender.color().unwrap().rgb.to_string(), "#FF0000");
+```
+*/
+pub trait HasSender {
+    /// The login of the user, if known
+    fn login(&self) -> Option<&str>;
+
+    /// The display name of the user, if set
+    fn display_name(&self) -> Option<&str> {
+        None
+    }
+
+    /// The id of the user, if known
+    fn user_id(&self) -> Option<u64> {
+        None
+    }
+
+    /// The color of the user, if set
+    fn color(&self) -> Option<Color> {
+        None
+    }
+}
+
+/// A message that has tags attached to it
+pub trait HasTags {
+    /// Get a view of parsable tags
+    fn tags(&self) -> Tags<'_>;
+}
+
+/**
+A message that has badges attached to it
+
+```
+# use trovochat::{messages::{HasBadges, Privmsg}, trovo::BadgeKind, FromIrcMessage as _};
+let input = "@badge-info=subscriber/8;badges=broadcaster/1,subscriber/6 :museun!museun@museun.tmi.trovo.tv PRIVMSG #museun :hello\r\n";
+let pm = Privmsg::from_irc(trovochat::irc::parse_one(input).unwrap().1).unwrap();
+
+assert_eq!(HasBadges::badges(&pm).len(), 2);
+assert_eq!(HasBadges::badge_info(&pm)[0].data, "8");
+assert!(pm.has_badge(BadgeKind::Broadcaster));
+assert!(!pm.has_badge(BadgeKind::Moderator));
+```
+*/
+pub trait HasBadges: HasTags {
+    /// Badges attached to this message
+    fn badges(&self) -> Vec<Badge<'_>> {
+        self.tags()
+            .get("badges")
+            .map(parse_badges)
+            .unwrap_or_default()
+    }
+
+    /// Metadata related to the badges, such as how many months the user has been subscribed
+    fn badge_info(&self) -> Vec<BadgeInfo<'_>> {
+        self.tags()
+            .get("badge-info")
+            .map(parse_badges)
+            .unwrap_or_default()
+    }
+
+    /// Whether a badge of this kind is attached to this message
+    fn has_badge(&self, kind: BadgeKind<'_>) -> bool {
+        self.tags()
+            .get("badges")
+            .into_iter()
+            .flat_map(parse_badges_iter)
+            .any(|badge| badge.kind == kind)
+    }
+}
+
+macro_rules! has_channel {
+    ($($ty:ident => $field:ident)*) => {
+        $(impl<'a> HasChannel for $ty<'a> {
+            fn channel(&self) -> &str {
+                self.$field()
+            }
+        })*
+    };
+}
+
+has_channel! {
+    ClearChat => channel
+    ClearMsg => channel
+    EndOfNames => channel
+    HostTarget => source
+    Join => channel
+    Names => channel
+    Notice => channel
+    Part => channel
+    Privmsg => channel
+    RoomState => channel
+    UserNotice => channel
+    UserState => channel
+}
+
+macro_rules! has_tags {
+    ($($ty:ident)*) => {
+        $(impl<'a> HasTags for $ty<'a> {
+            fn tags(&self) -> Tags<'_> {
+                self.tags()
+            }
+        })*
+    };
+}
+
+has_tags! {
+    ClearChat
+    ClearMsg
+    GlobalUserState
+    Notice
+    Privmsg
+    RoomState
+    UserNotice
+    UserState
+    Whisper
+}
+
+macro_rules! has_badges {
+    ($($ty:ident)*) => {
+        $(impl<'a> HasBadges for $ty<'a> {})*
+    };
+}
+
+has_badges! {
+    GlobalUserState
+    Privmsg
+    UserNotice
+    UserState
+    Whisper
+}
+
+macro_rules! has_sender {
+    (@tags $($ty:ident => |$msg:ident| $login:expr;)*) => {
+        $(impl<'a> HasSender for $ty<'a> {
+            fn login(&self) -> Option<&str> {
+                let $msg = self;
+                $login
+            }
+
+            fn display_name(&self) -> Option<&str> {
+                HasTags::tags(self).get("display-name")
+            }
+
+            fn user_id(&self) -> Option<u64> {
+                HasTags::tags(self).get_parsed("user-id")
+            }
+
+            fn color(&self) -> Option<Color> {
+                HasTags::tags(self).get_parsed("color")
+            }
+        })*
+    };
+
+    (@none $($ty:ident)*) => {
+        $(impl<'a> HasSender for $ty<'a> {
+            fn login(&self) -> Option<&str> {
+                Some(self.name())
+            }
+        })*
+    };
+}
+
+has_sender! {
+    @tags
+    ClearMsg => |msg| msg.login();
+    GlobalUserState => |msg| HasTags::tags(msg).get("login");
+    Privmsg => |msg| Some(msg.name());
+    UserNotice => |msg| msg.login();
+    UserState => |msg| HasTags::tags(msg).get("login");
+    Whisper => |msg| Some(msg.name());
+}
+
+has_sender! {
+    @none
+    Join
+    Part
+}
+
+impl<'a> Commands<'a> {
+    /// The channel this message happened on, if it has one
+    ///
+    /// See [HasChannel]
+    pub fn channel(&self) -> Option<&str> {
+        let msg: &dyn HasChannel = match self {
+            Self::ClearChat(msg) => msg,
+            Self::ClearMsg(msg) => msg,
+            Self::EndOfNames(msg) => msg,
+            Self::HostTarget(msg) => msg,
+            Self::Join(msg) => msg,
+            Self::Names(msg) => msg,
+            Self::Notice(msg) => msg,
+            Self::Part(msg) => msg,
+            Self::Privmsg(msg) => msg,
+            Self::RoomState(msg) => msg,
+            Self::UserNotice(msg) => msg,
+            Self::UserState(msg) => msg,
+            _ => return None,
+        };
+        Some(msg.channel())
+    }
+
+    /// The user this message was sent by (or is about), if it has one
+    ///
+    /// See [HasSender]
+    ///
+    /// ```
+    /// # use trovochat::{messages::Commands, FromIrcMessage as _};
+    /// let input = "@display-name=Museun :museun!museun@museun.tmi.trovo.tv PRIVMSG #museun :hello\r\n";
+    /// let msg = Commands::from_irc(trovochat::irc::parse_one(input).unwrap().1).unwrap();
+    ///
+    /// let sender = msg.sender().unwrap();
+    /// assert_eq!(sender.login(), Some("museun"));
+    /// assert_eq!(sender.display_name(), Some("Museun"));
+    /// assert_eq!(msg.channel(), Some("#museun"));
+    /// ```
+    pub fn sender(&self) -> Option<&dyn HasSender> {
+        let msg: &dyn HasSender = match self {
+            Self::ClearMsg(msg) => msg,
+            Self::GlobalUserState(msg) => msg,
+            Self::Join(msg) => msg,
+            Self::Part(msg) => msg,
+            Self::Privmsg(msg) => msg,
+            Self::UserNotice(msg) => msg,
+            Self::UserState(msg) => msg,
+            Self::Whisper(msg) => msg,
+            _ => return None,
+        };
+        Some(msg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FromIrcMessage as _;
+
+    fn parse(input: &str) -> Vec<Commands<'_>> {
+        crate::irc::parse(input)
+            .map(|msg| Commands::from_irc(msg.unwrap()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn channel() {
+        let input = ":museun!museun@museun.tmi.trovo.tv JOIN #museun\r\n\
+                     :tmi.trovo.tv HOSTTARGET #museun :shaken_bot 10\r\n\
+                     @login=museun :tmi.trovo.tv CLEARMSG #museun :hello\r\n\
+                     :tmi.trovo.tv 353 museun = #museun :museun shaken_bot\r\n\
+                     @user-id=1 :museun!museun@museun.tmi.trovo.tv WHISPER shaken_bot :hello\r\n\
+                     PING :1234\r\n";
+
+        let channels = parse(input)
+            .iter()
+            .map(|msg| msg.channel().map(ToString::to_string))
+            .collect::<Vec<_>>();
+
+        let museun = || Some("#museun".to_string());
+        assert_eq!(
+            channels,
+            vec![museun(), museun(), museun(), museun(), None, None]
+        );
+    }
+
+    #[test]
+    fn sender() {
+        let input = "@color=#FF0000;display-name=Museun;user-id=23196011 :museun!museun@museun.tmi.trovo.tv PRIVMSG #museun :hello\r\n\
+                     @display-name=Museun;user-id=23196011;login=museun;msg-id=raid :tmi.trovo.tv USERNOTICE #museun\r\n\
+                     @color=#00FF00;display-name=Museun;user-id=23196011 :museun!museun@museun.tmi.trovo.tv WHISPER shaken_bot :hello\r\n\
+                     :museun!museun@museun.tmi.trovo.tv PART #museun\r\n\
+                     @color=#0000FF;display-name=Shaken_Bot :tmi.trovo.tv USERSTATE #museun\r\n\
+                     :tmi.trovo.tv HOSTTARGET #museun :shaken_bot 10\r\n";
+
+        let senders = parse(input)
+            .iter()
+            .map(|msg| {
+                msg.sender().map(|sender| {
+                    (
+                        sender.login().map(ToString::to_string),
+                        sender.display_name().map(ToString::to_string),
+                        sender.user_id(),
+                        sender.color().map(|color| color.rgb.to_string()),
+                    )
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let museun = || Some("museun".to_string());
+        let display = |s: &str| Some(s.to_string());
+        assert_eq!(
+            senders,
+            vec![
+                Some((
+                    museun(),
+                    display("Museun"),
+                    Some(23_196_011),
+                    Some("#FF0000".to_string())
+                )),
+                Some((museun(), display("Museun"), Some(23_196_011), None)),
+                Some((
+                    museun(),
+                    display("Museun"),
+                    Some(23_196_011),
+                    Some("#00FF00".to_string())
+                )),
+                Some((museun(), None, None, None)),
+                Some((
+                    None,
+                    display("Shaken_Bot"),
+                    None,
+                    Some("#0000FF".to_string())
+                )),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn badges() {
+        let input = "@badge-info=subscriber/8;badges=moderator/1,subscriber/6 :tmi.trovo.tv USERSTATE #museun\r\n\
+                     @badges=staff/1 :museun!museun@museun.tmi.trovo.tv WHISPER shaken_bot :hello\r\n";
+
+        let msgs = parse(input);
+        let badges: Vec<&dyn HasBadges> = msgs
+            .iter()
+            .map(|msg| -> &dyn HasBadges {
+                match msg {
+                    Commands::UserState(msg) => msg,
+                    Commands::Whisper(msg) => msg,
+                    _ => unreachable!(),
+                }
+            })
+            .collect();
+
+        assert!(badges[0].has_badge(BadgeKind::Moderator));
+        assert!(!badges[0].has_badge(BadgeKind::Staff));
+        assert_eq!(badges[0].badges().len(), 2);
+        assert_eq!(badges[0].badge_info()[0].data, "8");
+
+        assert!(badges[1].has_badge(BadgeKind::Staff));
+        assert!(badges[1].badge_info().is_empty());
+    }
+}
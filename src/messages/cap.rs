@@ -14,6 +14,11 @@ pub enum Capability<'a> {
         /// The name of the requested capability
         &'a str,
     ),
+    /// This Capability is available, in response to a `CAP LS`
+    Listed(
+        /// The name of the capability
+        &'a str,
+    ),
 }
 
 /// Acknowledgement (or not) on a **CAPS** request
@@ -21,19 +26,42 @@ pub enum Capability<'a> {
 pub struct Cap<'a> {
     raw: MaybeOwned<'a>,
     capability: MaybeOwnedIndex,
-    acknowledged: bool,
+    subcommand: MaybeOwnedIndex,
+    continued: bool,
 }
 
 impl<'a> Cap<'a> {
     raw!();
 
     /// The parsed capability
+    ///
+    /// If the server sent several capabilities in one message, this is all of them. Use [Cap::capabilities()] to get each one.
     pub fn capability(&self) -> Capability<'_> {
-        let cap = &self.raw[self.capability];
-        if self.acknowledged {
-            Capability::Acknowledged(cap)
-        } else {
-            Capability::NotAcknowledged(cap)
+        self.wrap(&self.raw[self.capability])
+    }
+
+    /// An iterator over each capability in this message
+    pub fn capabilities(&self) -> impl Iterator<Item = Capability<'_>> + '_ {
+        self.raw[self.capability]
+            .split_whitespace()
+            .map(move |cap| self.wrap(cap))
+    }
+
+    /// Whether the server will send more capabilities in another message.
+    ///
+    /// This is only set for a multi-line `CAP LS` response.
+    pub fn is_continued(&self) -> bool {
+        self.continued
+    }
+
+    fn wrap<'b>(&self, cap: &'b str) -> Capability<'b> {
+        const ACK: &str = "ACK";
+        const LS: &str = "LS";
+
+        match &self.raw[self.subcommand] {
+            ACK => Capability::Acknowledged(cap),
+            LS => Capability::Listed(cap),
+            _ => Capability::NotAcknowledged(cap),
         }
     }
 }
@@ -42,13 +70,14 @@ impl<'a> FromIrcMessage<'a> for Cap<'a> {
     type Error = MessageError;
 
     fn from_irc(msg: IrcMessage<'a>) -> Result<Self, Self::Error> {
-        const ACK: &str = "ACK";
+        const CONTINUED: &str = "*";
 
         msg.expect_command(IrcMessage::CAP)?;
 
         let this = Self {
             capability: msg.expect_data_index()?,
-            acknowledged: msg.expect_arg(1)? == ACK,
+            subcommand: msg.expect_arg_index(1)?,
+            continued: matches!(msg.expect_arg(2), Ok(CONTINUED)),
             raw: msg.raw,
         };
 
@@ -61,7 +90,8 @@ impl<'a> FromIrcMessage<'a> for Cap<'a> {
 into_owned!(Cap {
     raw,
     capability,
-    acknowledged
+    subcommand,
+    continued
 });
 
 impl_custom_debug!(Cap { raw, capability });
@@ -104,4 +134,43 @@ mod tests {
             assert_eq!(cap.capability(), Capability::NotAcknowledged("foobar"));
         }
     }
+
+    #[test]
+    fn cap_listed() {
+        let input = ":tmi.trovo.tv CAP * LS * :trovo.tv/tags trovo.tv/commands\r\n\
+                     :tmi.trovo.tv CAP * LS :trovo.tv/membership\r\n";
+        let mut iter = parse(input)
+            .map(|s| s.unwrap())
+            .map(|msg| Cap::from_irc(msg).unwrap());
+
+        let cap = iter.next().unwrap();
+        assert!(cap.is_continued());
+        assert_eq!(
+            cap.capabilities().collect::<Vec<_>>(),
+            vec![
+                Capability::Listed("trovo.tv/tags"),
+                Capability::Listed("trovo.tv/commands")
+            ]
+        );
+
+        let cap = iter.next().unwrap();
+        assert!(!cap.is_continued());
+        assert_eq!(
+            cap.capabilities().collect::<Vec<_>>(),
+            vec![Capability::Listed("trovo.tv/membership")]
+        );
+    }
+
+    #[test]
+    fn cap_acknowledged_many() {
+        let input = ":tmi.trovo.tv CAP * ACK :trovo.tv/tags trovo.tv/commands\r\n";
+        let cap = Cap::from_irc(parse_one(input).unwrap().1).unwrap();
+        assert_eq!(
+            cap.capabilities().collect::<Vec<_>>(),
+            vec![
+                Capability::Acknowledged("trovo.tv/tags"),
+                Capability::Acknowledged("trovo.tv/commands")
+            ]
+        );
+    }
 }
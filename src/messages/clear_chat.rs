@@ -1,4 +1,30 @@
 use crate::{irc::*, MaybeOwned, MaybeOwnedIndex, Validator};
+use std::time::Duration;
+
+/// What a [ClearChat] did, retrieved via [ClearChat::action()]
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum ClearAction<'a> {
+    /// The entire chat was cleared
+    ClearAll,
+    /// A user was permanently banned
+    Ban {
+        /// The user that was banned
+        user: &'a str,
+        /// The id of the user that was banned
+        user_id: Option<u64>,
+    },
+    /// A user was timed out
+    Timeout {
+        /// The user that was timed out
+        user: &'a str,
+        /// The id of the user that was timed out
+        user_id: Option<u64>,
+        /// How long the timeout lasts
+        duration: Duration,
+    },
+}
 
 /// When a user's message(s) have been purged.
 ///
@@ -29,6 +55,29 @@ impl<'a> ClearChat<'a> {
         self.tags().get_parsed("ban-duration")
     }
 
+    /// The id of the user that was banned or timed out, if any
+    pub fn target_user_id(&self) -> Option<u64> {
+        self.tags().get_parsed("target-user-id")
+    }
+
+    /// Whether this cleared the entire chat, banned a user or timed out a user
+    pub fn action(&'a self) -> ClearAction<'a> {
+        let user = match self.name() {
+            Some(user) => user,
+            None => return ClearAction::ClearAll,
+        };
+
+        let user_id = self.target_user_id();
+        match self.ban_duration() {
+            Some(duration) => ClearAction::Timeout {
+                user,
+                user_id,
+                duration: Duration::from_secs(duration),
+            },
+            None => ClearAction::Ban { user, user_id },
+        }
+    }
+
     /// The room id this event happened on
     pub fn room_id(&self) -> Option<&str> {
         self.tags().get("room-id")
@@ -108,4 +157,32 @@ mod tests {
             assert!(cc.name().is_none());
         }
     }
+
+    #[test]
+    fn clear_chat_action() {
+        let input = ":tmi.trovo.tv CLEARCHAT #museun\r\n\
+                     @room-id=23196011;target-user-id=12345 :tmi.trovo.tv CLEARCHAT #museun :shaken_bot\r\n\
+                     @ban-duration=600;room-id=23196011;target-user-id=12345 :tmi.trovo.tv CLEARCHAT #museun :shaken_bot\r\n";
+
+        let actions = parse(input)
+            .map(|s| ClearChat::from_irc(s.unwrap()).unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(actions[0].action(), ClearAction::ClearAll);
+        assert_eq!(
+            actions[1].action(),
+            ClearAction::Ban {
+                user: "shaken_bot",
+                user_id: Some(12345)
+            }
+        );
+        assert_eq!(
+            actions[2].action(),
+            ClearAction::Timeout {
+                user: "shaken_bot",
+                user_id: Some(12345),
+                duration: Duration::from_secs(600)
+            }
+        );
+    }
 }
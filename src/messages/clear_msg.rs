@@ -1,5 +1,18 @@
 use crate::{irc::*, MaybeOwned, MaybeOwnedIndex, Validator};
 
+/// A message that was deleted, retrieved via [ClearMsg::deleted()]
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct DeletedMessage<'a> {
+    /// The login of the user who sent the message
+    pub login: &'a str,
+    /// The id (UUID) of the message
+    pub target_msg_id: &'a str,
+    /// The text of the message
+    pub message: &'a str,
+}
+
 /// When a single message has been removed from a channel.
 ///
 /// This is triggered via `/delete` on IRC.
@@ -32,6 +45,18 @@ impl<'a> ClearMsg<'a> {
     pub fn target_msg_id(&self) -> Option<&str> {
         self.tags().get("target-msg-id")
     }
+
+    /// Who sent the deleted message, its id and its text
+    ///
+    /// This returns None if any of those are missing.
+    pub fn deleted(&'a self) -> Option<DeletedMessage<'a>> {
+        DeletedMessage {
+            login: self.login()?,
+            target_msg_id: self.target_msg_id()?,
+            message: self.message()?,
+        }
+        .into()
+    }
 }
 
 impl<'a> FromIrcMessage<'a> for ClearMsg<'a> {
@@ -121,4 +146,26 @@ mod tests {
             assert_eq!(cm.target_msg_id().unwrap(), "abc-123-def");
         }
     }
+
+    #[test]
+    fn clear_msg_deleted() {
+        let input = "@login=shaken_bot;target-msg-id=aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa :tmi.trovo.tv CLEARMSG #museun :HeyGuys\r\n";
+        for msg in parse(input).map(|s| s.unwrap()) {
+            let cm = ClearMsg::from_irc(msg).unwrap();
+            assert_eq!(
+                cm.deleted(),
+                Some(DeletedMessage {
+                    login: "shaken_bot",
+                    target_msg_id: "aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa",
+                    message: "HeyGuys",
+                })
+            );
+        }
+
+        let input = ":tmi.trovo.tv CLEARMSG #museun :HeyGuys\r\n";
+        for msg in parse(input).map(|s| s.unwrap()) {
+            let cm = ClearMsg::from_irc(msg).unwrap();
+            assert!(cm.deleted().is_none());
+        }
+    }
 }
@@ -1,4 +1,4 @@
-use {super::*, crate::*};
+use {super::*, crate::irc::Reencode, crate::*};
 
 /// An enum of all possible Trovo messages.
 ///
@@ -45,6 +45,19 @@ pub enum Commands<'a> {
     UserState(UserState<'a>),
     /// A Whisper event occured
     Whisper(Whisper<'a>),
+    /// A Welcome event occured
+    Welcome(Welcome<'a>),
+    /// A Motd event occured
+    Motd(Motd<'a>),
+    /// A Names event occured
+    Names(Names<'a>),
+    /// An EndOfNames event occured
+    EndOfNames(EndOfNames<'a>),
+    /// An UnknownCommand event occured
+    UnknownCommand(UnknownCommand<'a>),
+    /// A user-defined message, parsed by a [Registry]
+    #[cfg_attr(feature = "serde", serde(skip_deserializing))]
+    Custom(Box<dyn CustomMessage>),
 }
 
 impl<'a> Commands<'a> {
@@ -70,8 +83,21 @@ impl<'a> Commands<'a> {
             Self::UserNotice(msg) => msg.raw(),
             Self::UserState(msg) => msg.raw(),
             Self::Whisper(msg) => msg.raw(),
+            Self::Welcome(msg) => msg.raw(),
+            Self::Motd(msg) => msg.raw(),
+            Self::Names(msg) => msg.raw(),
+            Self::EndOfNames(msg) => msg.raw(),
+            Self::UnknownCommand(msg) => msg.raw(),
+            Self::Custom(msg) => msg.raw(),
         }
     }
+
+    /// Re-encode this message in its canonical form, optionally rewriting its tags or prefix
+    ///
+    /// See [Reencode]
+    pub fn reencode(&self) -> Reencode<'_> {
+        Reencode::new(self.raw())
+    }
 }
 
 impl<'a> IntoOwned<'a> for Commands<'a> {
@@ -98,6 +124,12 @@ impl<'a> IntoOwned<'a> for Commands<'a> {
             Self::UserNotice(s) => Commands::UserNotice(s.into_owned()),
             Self::UserState(s) => Commands::UserState(s.into_owned()),
             Self::Whisper(s) => Commands::Whisper(s.into_owned()),
+            Self::Welcome(s) => Commands::Welcome(s.into_owned()),
+            Self::Motd(s) => Commands::Motd(s.into_owned()),
+            Self::Names(s) => Commands::Names(s.into_owned()),
+            Self::EndOfNames(s) => Commands::EndOfNames(s.into_owned()),
+            Self::UnknownCommand(s) => Commands::UnknownCommand(s.into_owned()),
+            Self::Custom(s) => Commands::Custom(s),
         }
     }
 }
@@ -132,6 +164,11 @@ impl<'a> FromIrcMessage<'a> for Commands<'a> {
             M::USER_NOTICE => map!(UserNotice),
             M::USER_STATE => map!(UserState),
             M::WHISPER => map!(Whisper),
+            M::YOUR_HOST | M::CREATED | M::MY_INFO => map!(Welcome),
+            M::MOTD_START | M::MOTD => map!(Motd),
+            M::NAMES_REPLY => map!(Names),
+            M::END_OF_NAMES => map!(EndOfNames),
+            M::UNKNOWN_COMMAND => map!(UnknownCommand),
             _ => Self::Raw(IrcMessage::from_irc(msg).expect("infallible conversion")),
         };
 
@@ -160,6 +197,12 @@ impl<'a> FromIrcMessage<'a> for Commands<'a> {
             Self::UserNotice(msg) => msg.into_inner(),
             Self::UserState(msg) => msg.into_inner(),
             Self::Whisper(msg) => msg.into_inner(),
+            Self::Welcome(msg) => msg.into_inner(),
+            Self::Motd(msg) => msg.into_inner(),
+            Self::Names(msg) => msg.into_inner(),
+            Self::EndOfNames(msg) => msg.into_inner(),
+            Self::UnknownCommand(msg) => msg.into_inner(),
+            Self::Custom(msg) => MaybeOwned::Owned(msg.raw().into()),
         }
     }
 }
@@ -196,6 +239,11 @@ from_other! {
     UserNotice
     UserState
     Whisper
+    Welcome
+    Motd
+    Names
+    EndOfNames
+    UnknownCommand
 }
 
 #[cfg(test)]
@@ -217,4 +265,48 @@ mod tests {
         let all = Commands::from_irc(msg).unwrap();
         assert!(matches!(all, Commands::Privmsg{..}));
     }
+
+    #[test]
+    fn owned_clone_is_shared() {
+        use crate::IntoOwned as _;
+
+        let input = ":test!test@test PRIVMSG #museun :this is a test\r\n";
+        let msg = IrcMessage::parse(MaybeOwned::Borrowed(input)).unwrap();
+        let left = Commands::from_irc(msg).unwrap().into_owned();
+        let right = left.clone();
+
+        assert_eq!(left, right);
+        assert_eq!(left.raw().as_ptr(), right.raw().as_ptr());
+
+        let msg = IrcMessage::parse(MaybeOwned::Owned(input.into())).unwrap();
+        let left = Commands::from_irc(msg).unwrap().into_owned();
+        let right = left.clone();
+        assert_eq!(left.raw().as_ptr(), right.raw().as_ptr());
+
+        let msg = IrcMessage::builder("PING").data("1234").build().unwrap();
+        assert!(msg.raw.is_shared());
+    }
+
+    #[test]
+    fn numerics() {
+        let input = ":tmi.trovo.tv 002 shaken_bot :Your host is tmi.trovo.tv\r\n\
+                     :tmi.trovo.tv 375 shaken_bot :-\r\n\
+                     :tmi.trovo.tv 372 shaken_bot :You are in a maze of twisty passages.\r\n\
+                     :shaken_bot.tmi.trovo.tv 353 shaken_bot = #museun :shaken_bot\r\n\
+                     :shaken_bot.tmi.trovo.tv 366 shaken_bot #museun :End of /NAMES list\r\n\
+                     :tmi.trovo.tv 421 shaken_bot WHO :Unknown command\r\n";
+
+        let mut iter =
+            crate::irc::parse(input).map(|msg| Commands::from_irc(msg.unwrap()).unwrap());
+        assert!(matches!(iter.next().unwrap(), Commands::Welcome { .. }));
+        assert!(matches!(iter.next().unwrap(), Commands::Motd { .. }));
+        assert!(matches!(iter.next().unwrap(), Commands::Motd { .. }));
+        assert!(matches!(iter.next().unwrap(), Commands::Names { .. }));
+        assert!(matches!(iter.next().unwrap(), Commands::EndOfNames { .. }));
+        assert!(matches!(
+            iter.next().unwrap(),
+            Commands::UnknownCommand { .. }
+        ));
+        assert!(iter.next().is_none());
+    }
 }
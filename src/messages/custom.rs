@@ -0,0 +1,328 @@
+use super::Commands;
+use crate::{FromIrcMessage, IntoOwned as _, IrcMessage, MessageError};
+use std::{any::Any, collections::HashMap};
+
+/**
+A user-defined message type that can be stored in [Commands::Custom]
+
+This is implemented for your own types, which can then be added to a [Registry]
+
+```
+# use trovochat::{messages::{Commands, CustomMessage, Registry}, FromIrcMessage, IrcMessage, MessageError, Validator as _};
+# use trovochat::maybe_owned::{MaybeOwned, MaybeOwnedIndex};
+#[derive(Debug, Clone, PartialEq)]
+struct Vip {
+    raw: MaybeOwned<'static>,
+    channel: MaybeOwnedIndex,
+}
+
+impl Vip {
+    fn channel(&self) -> &str {
+        &self.raw[self.channel]
+    }
+}
+
+impl FromIrcMessage<'static> for Vip {
+    type Error = MessageError;
+
+    fn from_irc(msg: IrcMessage<'static>) -> Result<Self, Self::Error> {
+        msg.expect_command("VIP")?;
+        let channel = msg.expect_arg_index(0)?;
+        Ok(Self { channel, raw: msg.into_inner() })
+    }
+
+    fn into_inner(self) -> MaybeOwned<'static> {
+        self.raw
+    }
+}
+
+impl CustomMessage for Vip {
+    fn raw(&self) -> &str {
+        &self.raw
+    }
+}
+
+let mut registry = Registry::new();
+registry.register::<Vip>("VIP");
+
+let input = ":tmi.trovo.tv VIP #museun\r\n";
+let msg = registry.parse(trovochat::irc::parse_one(input).unwrap().1).unwrap();
+
+match msg {
+    Commands::Custom(msg) => {
+        let vip = msg.downcast_ref::<Vip>().unwrap();
+        assert_eq!(vip.channel(), "#museun");
+    }
+    _ => unreachable!(),
+}
+```
+*/
+pub trait CustomMessage: AsCustomMessage + std::fmt::Debug + Send + Sync {
+    /// Get the raw message
+    fn raw(&self) -> &str;
+}
+
+/// Object-safe helpers for [CustomMessage]
+///
+/// This is implemented for any `Clone + PartialEq` [CustomMessage], so you shouldn't have to implement it.
+pub trait AsCustomMessage {
+    /// Get this message as an `Any`
+    fn as_any(&self) -> &dyn Any;
+
+    /// Convert this boxed message into a boxed `Any`
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+
+    /// Clone this message into a new box
+    fn clone_box(&self) -> Box<dyn CustomMessage>;
+
+    /// Compare this message with another message
+    fn eq_dyn(&self, other: &dyn CustomMessage) -> bool;
+}
+
+impl<T> AsCustomMessage for T
+where
+    T: CustomMessage + Clone + PartialEq + 'static,
+{
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+
+    fn clone_box(&self) -> Box<dyn CustomMessage> {
+        Box::new(self.clone())
+    }
+
+    fn eq_dyn(&self, other: &dyn CustomMessage) -> bool {
+        other.as_any().downcast_ref::<T>() == Some(self)
+    }
+}
+
+impl dyn CustomMessage {
+    /// Whether this message is a `T`
+    pub fn is<T: CustomMessage + 'static>(&self) -> bool {
+        self.as_any().is::<T>()
+    }
+
+    /// Get a reference to this message as a `T`, if it is one
+    pub fn downcast_ref<T: CustomMessage + 'static>(&self) -> Option<&T> {
+        self.as_any().downcast_ref()
+    }
+
+    /// Convert this message into a `T`, giving it back if it wasn't one
+    pub fn downcast<T: CustomMessage + 'static>(
+        self: Box<Self>,
+    ) -> Result<Box<T>, Box<dyn CustomMessage>> {
+        if self.is::<T>() {
+            Ok(self.into_any().downcast().expect("type was checked"))
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl Clone for Box<dyn CustomMessage> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+impl PartialEq for dyn CustomMessage {
+    fn eq(&self, other: &Self) -> bool {
+        self.eq_dyn(other)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl ::serde::Serialize for dyn CustomMessage {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ::serde::Serializer,
+    {
+        serializer.serialize_str(self.raw())
+    }
+}
+
+type ParseFn = fn(IrcMessage<'static>) -> Result<Box<dyn CustomMessage>, MessageError>;
+
+fn parse_custom<T>(msg: IrcMessage<'static>) -> Result<Box<dyn CustomMessage>, MessageError>
+where
+    T: CustomMessage + FromIrcMessage<'static> + 'static,
+    T::Error: Into<MessageError>,
+{
+    T::from_irc(msg)
+        .map(|msg| -> Box<dyn CustomMessage> { Box::new(msg) })
+        .map_err(Into::into)
+}
+
+/**
+A registry of user-defined message types, keyed by their IRC command
+
+Messages with a registered command are parsed into [Commands::Custom], instead of [Commands::Raw].
+
+Only commands that this crate doesn't already parse (those that would be a [Commands::Raw]) are looked up.
+
+See [CustomMessage] for an example.
+*/
+#[derive(Default, Clone)]
+pub struct Registry {
+    map: HashMap<String, ParseFn>,
+}
+
+impl std::fmt::Debug for Registry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.map.keys()).finish()
+    }
+}
+
+impl Registry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a type for this command, replacing any previously registered type
+    pub fn register<T>(&mut self, command: &str) -> &mut Self
+    where
+        T: CustomMessage + FromIrcMessage<'static> + 'static,
+        T::Error: Into<MessageError>,
+    {
+        self.map.insert(command.to_string(), parse_custom::<T>);
+        self
+    }
+
+    /// Whether a type has been registered for this command
+    pub fn contains(&self, command: &str) -> bool {
+        self.map.contains_key(command)
+    }
+
+    /// Whether no types have been registered
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Parse this message, using a registered type if there is one for its command
+    pub fn parse<'a>(&self, msg: IrcMessage<'a>) -> Result<Commands<'a>, MessageError> {
+        match Commands::from_irc(msg)? {
+            Commands::Raw(msg) => match self.parse_custom(&msg) {
+                Some(custom) => custom.map(Commands::Custom),
+                None => Ok(Commands::Raw(msg)),
+            },
+            msg => Ok(msg),
+        }
+    }
+
+    /// Parse this message as a registered type
+    ///
+    /// This returns None if no type was registered for its command.
+    pub fn parse_custom(
+        &self,
+        msg: &IrcMessage<'_>,
+    ) -> Option<Result<Box<dyn CustomMessage>, MessageError>> {
+        let parse = self.map.get(msg.get_command())?;
+        Some(parse(msg.clone().into_owned()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MaybeOwned, MaybeOwnedIndex, Validator as _};
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Vip {
+        raw: MaybeOwned<'static>,
+        channel: MaybeOwnedIndex,
+    }
+
+    impl FromIrcMessage<'static> for Vip {
+        type Error = MessageError;
+
+        fn from_irc(msg: IrcMessage<'static>) -> Result<Self, Self::Error> {
+            msg.expect_command("VIP")?;
+            let channel = msg.expect_arg_index(0)?;
+            Ok(Self {
+                channel,
+                raw: msg.into_inner(),
+            })
+        }
+
+        fn into_inner(self) -> MaybeOwned<'static> {
+            self.raw
+        }
+    }
+
+    impl CustomMessage for Vip {
+        fn raw(&self) -> &str {
+            &self.raw
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Other;
+
+    impl CustomMessage for Other {
+        fn raw(&self) -> &str {
+            ""
+        }
+    }
+
+    fn registry() -> Registry {
+        let mut registry = Registry::new();
+        registry.register::<Vip>("VIP");
+        registry
+    }
+
+    #[test]
+    fn parse() {
+        let registry = registry();
+        assert!(registry.contains("VIP"));
+
+        let input = ":tmi.trovo.tv VIP #museun\r\n\
+                     :tmi.trovo.tv UNVIP #museun\r\n\
+                     :museun!museun@museun.tmi.trovo.tv JOIN #museun\r\n";
+
+        let msgs = crate::irc::parse(input)
+            .map(|msg| registry.parse(msg.unwrap()).unwrap())
+            .collect::<Vec<_>>();
+
+        assert!(matches!(&msgs[0], Commands::Custom(msg) if msg.is::<Vip>()));
+        assert!(matches!(msgs[1], Commands::Raw { .. }));
+        assert!(matches!(msgs[2], Commands::Join { .. }));
+
+        assert_eq!(msgs[0].raw(), ":tmi.trovo.tv VIP #museun\r\n");
+    }
+
+    #[test]
+    fn parse_error() {
+        let input = ":tmi.trovo.tv VIP\r\n";
+        let msg = crate::irc::parse_one(input).unwrap().1;
+        assert!(matches!(
+            registry().parse(msg).unwrap_err(),
+            MessageError::ExpectedArg { pos: 0 }
+        ));
+    }
+
+    #[test]
+    fn downcast() {
+        let input = ":tmi.trovo.tv VIP #museun\r\n";
+        let msg = registry()
+            .parse(crate::irc::parse_one(input).unwrap().1)
+            .unwrap();
+
+        let owned = crate::IntoOwned::into_owned(msg.clone());
+        assert_eq!(owned, msg);
+
+        let custom = match owned {
+            Commands::Custom(custom) => custom,
+            _ => unreachable!(),
+        };
+
+        assert!(custom.downcast_ref::<Other>().is_none());
+        let custom = custom.downcast::<Other>().unwrap_err();
+        let vip = custom.downcast::<Vip>().unwrap();
+        assert_eq!(&vip.raw[vip.channel], "#museun");
+    }
+}
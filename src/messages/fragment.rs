@@ -0,0 +1,251 @@
+use crate::trovo::Emotes;
+
+/// A piece of a [Privmsg](super::Privmsg), retrieved via [Privmsg::fragments()](super::Privmsg::fragments)
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum Fragment<'a> {
+    /// Plain text, including any whitespace
+    Text(&'a str),
+    /// An emote
+    Emote {
+        /// The id of the emote, e.g. `Kappa = 25`
+        id: usize,
+        /// The text that the emote replaces, e.g. `Kappa`
+        name: &'a str,
+    },
+    /// A mention of a user, without the leading `@`
+    Mention(&'a str),
+    /// A `http://` or `https://` link
+    Link(&'a str),
+    /// A cheermote, e.g. `Cheer100`
+    Cheer {
+        /// The cheermote prefix, e.g. `Cheer`
+        prefix: &'a str,
+        /// The amount of bits, e.g. `100`
+        amount: u64,
+    },
+}
+
+impl<'a> Fragment<'a> {
+    /// The text from the message that this fragment covers
+    ///
+    /// For mentions this doesn't include the `@`, and for cheers this is just the prefix.
+    pub fn as_str(&self) -> &'a str {
+        match *self {
+            Self::Text(s) | Self::Mention(s) | Self::Link(s) => s,
+            Self::Emote { name, .. } => name,
+            Self::Cheer { prefix, .. } => prefix,
+        }
+    }
+}
+
+/// Split `data` into fragments, using the `emotes` tag. Cheers are only looked for if `cheers` is true.
+pub(super) fn parse_fragments<'a>(data: &'a str, emotes: &str, cheers: bool) -> Vec<Fragment<'a>> {
+    // Trovo's emote ranges are inclusive code point offsets,
+    // so map each code point to its byte offset (with the end of the string as the last one)
+    let offsets = data
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(data.len()))
+        .collect::<Vec<_>>();
+
+    let mut spans = Emotes::parse(emotes)
+        .flat_map(|emote| {
+            let id = emote.id;
+            emote.ranges.into_iter().map(move |range| (id, range))
+        })
+        .filter_map(|(id, range)| {
+            let start = *offsets.get(range.start as usize)?;
+            let end = *offsets.get(range.end as usize + 1)?;
+            if start < end {
+                Some((id, start, end))
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    spans.sort_by_key(|&(_, start, _)| start);
+
+    let mut out = vec![];
+    let mut pos = 0;
+    for (id, start, end) in spans {
+        // skip any overlapping ranges
+        if start < pos {
+            continue;
+        }
+        parse_words(data, pos, start, cheers, &mut out);
+        out.push(Fragment::Emote {
+            id,
+            name: &data[start..end],
+        });
+        pos = end;
+    }
+    parse_words(data, pos, data.len(), cheers, &mut out);
+
+    out
+}
+
+// find the mentions, links and cheers in text between the emotes
+fn parse_words<'a>(
+    data: &'a str,
+    start: usize,
+    end: usize,
+    cheers: bool,
+    out: &mut Vec<Fragment<'a>>,
+) {
+    let mut text_start = start;
+    let mut pos = start;
+
+    for word in data[start..end].split(' ') {
+        let word_start = pos;
+        pos += word.len() + 1;
+
+        let (fragment, len) = match classify(word, cheers) {
+            Some(found) => found,
+            None => continue,
+        };
+
+        if text_start < word_start {
+            out.push(Fragment::Text(&data[text_start..word_start]));
+        }
+        out.push(fragment);
+        text_start = word_start + len;
+    }
+
+    if text_start < end {
+        out.push(Fragment::Text(&data[text_start..end]));
+    }
+}
+
+// returns the fragment, and how many bytes of the word it used
+fn classify(word: &str, cheers: bool) -> Option<(Fragment<'_>, usize)> {
+    if word.starts_with("http://") || word.starts_with("https://") {
+        return Some((Fragment::Link(word), word.len()));
+    }
+
+    if let Some(name) = word.strip_prefix('@') {
+        let len = name
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(name.len());
+        if len == 0 {
+            return None;
+        }
+        return Some((Fragment::Mention(&name[..len]), len + 1));
+    }
+
+    if cheers {
+        let split = word.find(|c: char| c.is_ascii_digit())?;
+        let (prefix, amount) = word.split_at(split);
+        if prefix.is_empty() || !prefix.chars().all(|c| c.is_ascii_alphabetic()) {
+            return None;
+        }
+        if !amount.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let amount = amount.parse().ok().filter(|&n| n > 0)?;
+        return Some((Fragment::Cheer { prefix, amount }, word.len()));
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_text() {
+        assert_eq!(
+            parse_fragments("hello world", "", false),
+            vec![Fragment::Text("hello world")]
+        );
+        assert!(parse_fragments("", "", false).is_empty());
+    }
+
+    #[test]
+    fn emotes() {
+        assert_eq!(
+            parse_fragments("Kappa Kappa VoHiYo", "25:0-4,6-10/81274:12-17", false),
+            vec![
+                Fragment::Emote {
+                    id: 25,
+                    name: "Kappa"
+                },
+                Fragment::Text(" "),
+                Fragment::Emote {
+                    id: 25,
+                    name: "Kappa"
+                },
+                Fragment::Text(" "),
+                Fragment::Emote {
+                    id: 81274,
+                    name: "VoHiYo"
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn multibyte_offsets() {
+        // each of these is more than one byte, but one code point
+        let input = "\u{1F468}\u{00E9} Kappa \u{2764} Kappa";
+        assert_eq!(
+            parse_fragments(input, "25:3-7,11-15", false),
+            vec![
+                Fragment::Text("\u{1F468}\u{00E9} "),
+                Fragment::Emote {
+                    id: 25,
+                    name: "Kappa"
+                },
+                Fragment::Text(" \u{2764} "),
+                Fragment::Emote {
+                    id: 25,
+                    name: "Kappa"
+                },
+            ]
+        );
+
+        // out of range emotes are ignored
+        assert_eq!(
+            parse_fragments("\u{2764} Kappa", "25:2-6/26:2-10", false),
+            vec![
+                Fragment::Text("\u{2764} "),
+                Fragment::Emote {
+                    id: 25,
+                    name: "Kappa"
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn mentions_links_and_cheers() {
+        let input = "hey @museun, look at https://example.com cheer100 Kappa";
+        assert_eq!(
+            parse_fragments(input, "25:50-54", true),
+            vec![
+                Fragment::Text("hey "),
+                Fragment::Mention("museun"),
+                Fragment::Text(", look at "),
+                Fragment::Link("https://example.com"),
+                Fragment::Text(" "),
+                Fragment::Cheer {
+                    prefix: "cheer",
+                    amount: 100
+                },
+                Fragment::Text(" "),
+                Fragment::Emote {
+                    id: 25,
+                    name: "Kappa"
+                },
+            ]
+        );
+
+        // cheers are only found when the message has bits
+        assert_eq!(
+            parse_fragments("cheer100 @ a", "", false),
+            vec![Fragment::Text("cheer100 @ a")]
+        );
+    }
+}
@@ -0,0 +1,214 @@
+use super::{ClearAction, ClearChat, ClearMsg, Commands};
+use std::time::Duration;
+
+/**
+A moderation action, from either a [ClearChat] or a [ClearMsg]
+
+This is owned, so it can be kept around (or sent elsewhere) after the message is gone.
+
+```
+# use trovochat::{messages::{Commands, ModerationEvent}, FromIrcMessage as _};
+let input = "@ban-duration=600 :tmi.trovo.tv CLEARCHAT #museun :shaken_bot\r\n\
+             @login=shaken_bot;target-msg-id=aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa :tmi.trovo.tv CLEARMSG #museun :HeyGuys\r\n\
+             :museun!museun@museun.tmi.trovo.tv PRIVMSG #museun :hello\r\n";
+
+let events = trovochat::irc::parse(input)
+    .map(|msg| Commands::from_irc(msg.unwrap()).unwrap())
+    .filter_map(|msg| ModerationEvent::from_commands(&msg))
+    .collect::<Vec<_>>();
+
+assert_eq!(events.len(), 2);
+assert!(matches!(events[0], ModerationEvent::Timeout { .. }));
+assert!(matches!(events[1], ModerationEvent::Delete { .. }));
+assert_eq!(events[1].user(), Some("shaken_bot"));
+```
+*/
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum ModerationEvent {
+    /// The entire chat was cleared
+    ClearAll {
+        /// The channel this happened on
+        channel: String,
+    },
+    /// A user was permanently banned
+    Ban {
+        /// The channel this happened on
+        channel: String,
+        /// The user that was banned
+        user: String,
+        /// The id of the user that was banned
+        user_id: Option<u64>,
+    },
+    /// A user was timed out
+    Timeout {
+        /// The channel this happened on
+        channel: String,
+        /// The user that was timed out
+        user: String,
+        /// The id of the user that was timed out
+        user_id: Option<u64>,
+        /// How long the timeout lasts
+        duration: Duration,
+    },
+    /// A single message was deleted
+    Delete {
+        /// The channel this happened on
+        channel: String,
+        /// The login of the user who sent the message
+        user: String,
+        /// The id (UUID) of the message
+        target_msg_id: String,
+        /// The text of the message
+        message: String,
+    },
+}
+
+impl ModerationEvent {
+    /// Get the moderation event from this message, if it was a [ClearChat] or a (complete) [ClearMsg]
+    pub fn from_commands(msg: &Commands<'_>) -> Option<Self> {
+        match msg {
+            Commands::ClearChat(msg) => Some(Self::from_clear_chat(msg)),
+            Commands::ClearMsg(msg) => Self::from_clear_msg(msg),
+            _ => None,
+        }
+    }
+
+    /// Get the moderation event from this [ClearChat]
+    pub fn from_clear_chat(msg: &ClearChat<'_>) -> Self {
+        let channel = msg.channel().to_string();
+        match msg.action() {
+            ClearAction::ClearAll => Self::ClearAll { channel },
+            ClearAction::Ban { user, user_id } => Self::Ban {
+                channel,
+                user: user.to_string(),
+                user_id,
+            },
+            ClearAction::Timeout {
+                user,
+                user_id,
+                duration,
+            } => Self::Timeout {
+                channel,
+                user: user.to_string(),
+                user_id,
+                duration,
+            },
+        }
+    }
+
+    /// Get the moderation event from this [ClearMsg]
+    ///
+    /// This returns None if the user, message id or text is missing.
+    pub fn from_clear_msg(msg: &ClearMsg<'_>) -> Option<Self> {
+        let deleted = msg.deleted()?;
+        Self::Delete {
+            channel: msg.channel().to_string(),
+            user: deleted.login.to_string(),
+            target_msg_id: deleted.target_msg_id.to_string(),
+            message: deleted.message.to_string(),
+        }
+        .into()
+    }
+
+    /// The channel this happened on
+    pub fn channel(&self) -> &str {
+        match self {
+            Self::ClearAll { channel }
+            | Self::Ban { channel, .. }
+            | Self::Timeout { channel, .. }
+            | Self::Delete { channel, .. } => channel,
+        }
+    }
+
+    /// The user this was aimed at, if any
+    pub fn user(&self) -> Option<&str> {
+        match self {
+            Self::ClearAll { .. } => None,
+            Self::Ban { user, .. } | Self::Timeout { user, .. } | Self::Delete { user, .. } => {
+                Some(user)
+            }
+        }
+    }
+}
+
+cfg_async! {
+    /// Filter a `Stream` of [Commands] (such as the [AsyncRunner](crate::AsyncRunner)) down to just the [ModerationEvent]s
+    pub fn moderation_events<S>(stream: S) -> impl futures_lite::Stream<Item = ModerationEvent>
+    where
+        S: futures_lite::Stream<Item = Commands<'static>>,
+    {
+        futures_lite::StreamExt::filter_map(stream, |msg| ModerationEvent::from_commands(&msg))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FromIrcMessage as _;
+
+    fn events(input: &str) -> Vec<ModerationEvent> {
+        crate::irc::parse(input)
+            .map(|msg| Commands::from_irc(msg.unwrap()).unwrap())
+            .filter_map(|msg| ModerationEvent::from_commands(&msg))
+            .collect()
+    }
+
+    #[test]
+    fn moderation_event() {
+        let input = ":tmi.trovo.tv CLEARCHAT #museun\r\n\
+                     @target-user-id=12345 :tmi.trovo.tv CLEARCHAT #museun :shaken_bot\r\n\
+                     @ban-duration=10 :tmi.trovo.tv CLEARCHAT #museun :shaken_bot\r\n\
+                     @login=shaken_bot;target-msg-id=aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa :tmi.trovo.tv CLEARMSG #museun :HeyGuys\r\n\
+                     :tmi.trovo.tv CLEARMSG #museun :HeyGuys\r\n\
+                     PING :1234\r\n";
+
+        let channel = || "#museun".to_string();
+        let user = || "shaken_bot".to_string();
+
+        assert_eq!(
+            events(input),
+            vec![
+                ModerationEvent::ClearAll { channel: channel() },
+                ModerationEvent::Ban {
+                    channel: channel(),
+                    user: user(),
+                    user_id: Some(12345)
+                },
+                ModerationEvent::Timeout {
+                    channel: channel(),
+                    user: user(),
+                    user_id: None,
+                    duration: Duration::from_secs(10)
+                },
+                ModerationEvent::Delete {
+                    channel: channel(),
+                    user: user(),
+                    target_msg_id: "aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa".to_string(),
+                    message: "HeyGuys".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn moderation_events_stream() {
+        use futures_lite::StreamExt as _;
+
+        let input = ":tmi.trovo.tv CLEARCHAT #museun\r\nPING :1234\r\n";
+        let messages = crate::irc::parse(input)
+            .map(|msg| crate::IntoOwned::into_owned(Commands::from_irc(msg.unwrap()).unwrap()))
+            .collect::<Vec<_>>();
+
+        let stream = moderation_events(futures_lite::stream::iter(messages));
+        let events = futures_lite::future::block_on(stream.collect::<Vec<_>>());
+        assert_eq!(
+            events,
+            vec![ModerationEvent::ClearAll {
+                channel: "#museun".to_string()
+            }]
+        );
+    }
+}
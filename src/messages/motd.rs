@@ -0,0 +1,100 @@
+use crate::{irc::*, MaybeOwned, MaybeOwnedIndex, Validator};
+
+/// A line of the message of the day -- `375` and `372`
+///
+/// The end of the message of the day (`376`) is the [Ready](super::Ready) message
+#[derive(Clone, PartialEq)]
+pub struct Motd<'a> {
+    raw: MaybeOwned<'a>,
+    start: bool,
+    username: MaybeOwnedIndex,
+    data: Option<MaybeOwnedIndex>,
+}
+
+impl<'a> Motd<'a> {
+    raw!();
+    str_field!(
+        /// The name the server will refer to you as
+        username
+    );
+    opt_str_field!(
+        /// The text of this line
+        data
+    );
+
+    /// Whether this is the start of the message of the day -- `375`
+    pub fn is_start(&self) -> bool {
+        self.start
+    }
+}
+
+impl<'a> FromIrcMessage<'a> for Motd<'a> {
+    type Error = MessageError;
+
+    fn from_irc(msg: IrcMessage<'a>) -> Result<Self, Self::Error> {
+        let start = msg.get_command() == IrcMessage::MOTD_START;
+        if !start {
+            msg.expect_command(IrcMessage::MOTD)?;
+        }
+
+        let this = Self {
+            start,
+            username: msg.expect_arg_index(0)?,
+            data: msg.data,
+            raw: msg.raw,
+        };
+
+        Ok(this)
+    }
+
+    into_inner_raw!();
+}
+
+into_owned!(Motd {
+    raw,
+    start,
+    username,
+    data
+});
+impl_custom_debug!(Motd {
+    raw,
+    is_start,
+    username,
+    data
+});
+serde_struct!(Motd {
+    raw,
+    is_start,
+    username,
+    data
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn motd_serde() {
+        let input = ":tmi.trovo.tv 372 shaken_bot :You are in a maze of twisty passages.\r\n";
+        crate::serde::round_trip_json::<Motd>(input);
+        crate::serde::round_trip_rmp::<Motd>(input);
+    }
+
+    #[test]
+    fn motd() {
+        let input = ":tmi.trovo.tv 375 shaken_bot :-\r\n\
+                     :tmi.trovo.tv 372 shaken_bot :You are in a maze of twisty passages.\r\n";
+
+        let mut iter = parse(input).map(|s| Motd::from_irc(s.unwrap()).unwrap());
+
+        let msg = iter.next().unwrap();
+        assert!(msg.is_start());
+        assert_eq!(msg.username(), "shaken_bot");
+        assert_eq!(msg.data().unwrap(), "-");
+
+        let msg = iter.next().unwrap();
+        assert!(!msg.is_start());
+        assert_eq!(msg.data().unwrap(), "You are in a maze of twisty passages.");
+    }
+}
@@ -0,0 +1,180 @@
+use crate::{irc::*, MaybeOwned, MaybeOwnedIndex, Validator};
+
+/// A list of users on a channel -- `353`
+///
+/// This may be split across several messages, and is ended by an [EndOfNames]
+#[derive(Clone, PartialEq)]
+pub struct Names<'a> {
+    raw: MaybeOwned<'a>,
+    username: MaybeOwnedIndex,
+    channel: MaybeOwnedIndex,
+    data: Option<MaybeOwnedIndex>,
+}
+
+impl<'a> Names<'a> {
+    raw!();
+    str_field!(
+        /// The name the server will refer to you as
+        username
+    );
+    str_field!(
+        /// The channel these users are on
+        channel
+    );
+    opt_str_field!(
+        /// The space-separated list of users
+        data
+    );
+
+    /// An iterator over the names of the users
+    pub fn names(&self) -> impl Iterator<Item = &str> + '_ {
+        self.data().unwrap_or_default().split_ascii_whitespace()
+    }
+}
+
+impl<'a> FromIrcMessage<'a> for Names<'a> {
+    type Error = MessageError;
+
+    fn from_irc(msg: IrcMessage<'a>) -> Result<Self, Self::Error> {
+        msg.expect_command(IrcMessage::NAMES_REPLY)?;
+
+        // <username> [=|*|@] <channel> :<names>
+        let channel = match msg.expect_arg(1)? {
+            "=" | "*" | "@" => msg.expect_arg_index(2)?,
+            _ => msg.expect_arg_index(1)?,
+        };
+
+        let this = Self {
+            username: msg.expect_arg_index(0)?,
+            channel,
+            data: msg.data,
+            raw: msg.raw,
+        };
+
+        Ok(this)
+    }
+
+    into_inner_raw!();
+}
+
+into_owned!(Names {
+    raw,
+    username,
+    channel,
+    data
+});
+
+impl_custom_debug!(Names {
+    raw,
+    username,
+    channel,
+    data
+});
+
+serde_struct!(Names {
+    raw,
+    username,
+    channel,
+    data
+});
+
+/// The end of the list of users on a channel -- `366`
+#[derive(Clone, PartialEq)]
+pub struct EndOfNames<'a> {
+    raw: MaybeOwned<'a>,
+    username: MaybeOwnedIndex,
+    channel: MaybeOwnedIndex,
+}
+
+impl<'a> EndOfNames<'a> {
+    raw!();
+    str_field!(
+        /// The name the server will refer to you as
+        username
+    );
+    str_field!(
+        /// The channel the list of users was for
+        channel
+    );
+}
+
+impl<'a> FromIrcMessage<'a> for EndOfNames<'a> {
+    type Error = MessageError;
+
+    fn from_irc(msg: IrcMessage<'a>) -> Result<Self, Self::Error> {
+        msg.expect_command(IrcMessage::END_OF_NAMES)?;
+
+        let this = Self {
+            username: msg.expect_arg_index(0)?,
+            channel: msg.expect_arg_index(1)?,
+            raw: msg.raw,
+        };
+
+        Ok(this)
+    }
+
+    into_inner_raw!();
+}
+
+into_owned!(EndOfNames {
+    raw,
+    username,
+    channel
+});
+impl_custom_debug!(EndOfNames {
+    raw,
+    username,
+    channel
+});
+serde_struct!(EndOfNames {
+    raw,
+    username,
+    channel
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn names_serde() {
+        let input = ":shaken_bot.tmi.trovo.tv 353 shaken_bot = #museun :shaken_bot museun\r\n";
+        crate::serde::round_trip_json::<Names>(input);
+        crate::serde::round_trip_rmp::<Names>(input);
+
+        let input = ":shaken_bot.tmi.trovo.tv 366 shaken_bot #museun :End of /NAMES list\r\n";
+        crate::serde::round_trip_json::<EndOfNames>(input);
+        crate::serde::round_trip_rmp::<EndOfNames>(input);
+    }
+
+    #[test]
+    fn names() {
+        let input = ":shaken_bot.tmi.trovo.tv 353 shaken_bot = #museun :shaken_bot museun\r\n\
+                     :shaken_bot.tmi.trovo.tv 353 shaken_bot #museun :foo\r\n";
+
+        let mut iter = parse(input).map(|s| Names::from_irc(s.unwrap()).unwrap());
+
+        let msg = iter.next().unwrap();
+        assert_eq!(msg.username(), "shaken_bot");
+        assert_eq!(msg.channel(), "#museun");
+        assert_eq!(
+            msg.names().collect::<Vec<_>>(),
+            vec!["shaken_bot", "museun"]
+        );
+
+        let msg = iter.next().unwrap();
+        assert_eq!(msg.channel(), "#museun");
+        assert_eq!(msg.names().collect::<Vec<_>>(), vec!["foo"]);
+    }
+
+    #[test]
+    fn end_of_names() {
+        let input = ":shaken_bot.tmi.trovo.tv 366 shaken_bot #museun :End of /NAMES list\r\n";
+        for msg in parse(input).map(|s| s.unwrap()) {
+            let msg = EndOfNames::from_irc(msg).unwrap();
+            assert_eq!(msg.username(), "shaken_bot");
+            assert_eq!(msg.channel(), "#museun");
+        }
+    }
+}
@@ -1,7 +1,12 @@
+use super::{
+    fragment::{parse_fragments, Fragment},
+    SubPlan,
+};
 use crate::{irc::*, MaybeOwned, MaybeOwnedIndex, Validator};
 
 use crate::trovo::{
     parse_badges, parse_badges_iter, parse_emotes, Badge, BadgeInfo, BadgeKind, Color, Emotes,
+    PredictionChoice,
 };
 
 /// Some PRIVMSGs are considered 'CTCP' (client-to-client protocol)
@@ -19,6 +24,35 @@ pub enum Ctcp<'a> {
     },
 }
 
+/// The message that a [Privmsg] is replying to, retrieved via [Privmsg::reply_parent()]
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplyParent<'a> {
+    /// The id (UUID) of the parent message
+    pub msg_id: &'a str,
+    /// The id of the user who sent the parent message, if provided
+    pub user_id: Option<u64>,
+    /// The login of the user who sent the parent message
+    pub user_login: &'a str,
+    /// The display name of the user who sent the parent message
+    pub display_name: MaybeOwned<'a>,
+    /// The (unescaped) body of the parent message
+    pub body: MaybeOwned<'a>,
+}
+
+impl<'a> ReplyParent<'a> {
+    /// Reply to the parent message on this channel
+    ///
+    /// This keeps a reply in the same thread as the parent message
+    pub fn reply<'b>(
+        &'b self,
+        channel: &'b str,
+        msg: &'b str,
+    ) -> crate::commands::types::Reply<'b> {
+        crate::commands::reply(channel, self.msg_id, msg)
+    }
+}
+
 /// Message sent by a user
 #[derive(Clone, PartialEq)]
 pub struct Privmsg<'a> {
@@ -230,6 +264,123 @@ impl<'a> Privmsg<'a> {
         self.tags().get("msg-id")
     }
 
+    /// The exact number of months the user has been subscribed, from the `badge-info`
+    pub fn subscriber_months(&self) -> Option<u64> {
+        self.find_badge("badge-info", BadgeKind::Subscriber)?
+            .number()
+    }
+
+    /// The user's founder number, from the `badge-info`
+    pub fn founder_number(&self) -> Option<u64> {
+        self.find_badge("badge-info", BadgeKind::Founder)?.number()
+    }
+
+    /// The prediction outcome the user picked, from their `predictions` badge
+    pub fn prediction(&'a self) -> Option<PredictionChoice<'a>> {
+        self.find_badge("badges", BadgeKind::Predictions)?
+            .prediction()
+    }
+
+    /// The user's subscription tier, from their `subscriber` badge
+    pub fn sub_tier(&self) -> Option<SubPlan<'static>> {
+        self.find_badge("badges", BadgeKind::Subscriber)?.sub_tier()
+    }
+
+    /// A unique id (UUID) attached to this message
+    pub fn id(&self) -> Option<&str> {
+        self.tags().get("id")
+    }
+
+    /** The message this message is replying to, if it was a reply
+
+    ```
+    # use trovochat::{messages::Privmsg, FromIrcMessage as _};
+    let input = "@id=bbbbbbbb-bbbb-bbbb-bbbb-bbbbbbbbbbbb;reply-parent-display-name=Museun;reply-parent-msg-body=hello\\sworld;reply-parent-msg-id=aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa;reply-parent-user-id=23196011;reply-parent-user-login=museun :shaken_bot!shaken_bot@shaken_bot.tmi.trovo.tv PRIVMSG #museun :@museun hi\r\n";
+    let msg = Privmsg::from_irc(trovochat::irc::parse_one(input).unwrap().1).unwrap();
+
+    let parent = msg.reply_parent().unwrap();
+    assert_eq!(parent.msg_id, "aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa");
+    assert_eq!(parent.user_login, "museun");
+    assert_eq!(parent.body, "hello world");
+    ```
+    */
+    pub fn reply_parent(&'a self) -> Option<ReplyParent<'a>> {
+        let tags = self.tags();
+        ReplyParent {
+            msg_id: tags.get("reply-parent-msg-id")?,
+            user_id: tags.get_parsed("reply-parent-user-id"),
+            user_login: tags.get("reply-parent-user-login")?,
+            display_name: tags.get_unescaped("reply-parent-display-name")?,
+            body: tags.get_unescaped("reply-parent-msg-body")?,
+        }
+        .into()
+    }
+
+    /** Reply to this message, continuing its thread
+
+    If this message was a reply, this replies to its parent, otherwise this replies to this message.
+
+    This returns None if the message has no id to reply to.
+
+    ```
+    # use trovochat::{messages::Privmsg, FromIrcMessage as _, Encodable as _};
+    let input = "@id=bbbbbbbb-bbbb-bbbb-bbbb-bbbbbbbbbbbb;reply-parent-display-name=Museun;reply-parent-msg-body=hello;reply-parent-msg-id=aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa;reply-parent-user-login=museun :shaken_bot!shaken_bot@shaken_bot.tmi.trovo.tv PRIVMSG #museun :@museun hi\r\n";
+    let msg = Privmsg::from_irc(trovochat::irc::parse_one(input).unwrap().1).unwrap();
+
+    let mut out = vec![];
+    msg.reply_in_thread("hello again").unwrap().encode(&mut out).unwrap();
+    assert_eq!(
+        std::str::from_utf8(&out).unwrap(),
+        "@reply-parent-msg-id=aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa PRIVMSG #museun :hello again\r\n"
+    );
+    ```
+    */
+    pub fn reply_in_thread<'b>(
+        &'b self,
+        msg: &'b str,
+    ) -> Option<crate::commands::types::Reply<'b>> {
+        let msg_id = match self.tags().get("reply-parent-msg-id") {
+            Some(id) => id,
+            None => self.id()?,
+        };
+        crate::commands::reply(self.channel(), msg_id, msg).into()
+    }
+
+    /** The message split into text, emotes, mentions, links and cheers, in order
+
+    This converts Trovo's emote positions (which count code points) into byte offsets, so it is safe for non-ASCII messages.
+
+    Cheers are only looked for if the message has bits attached.
+
+    ```
+    # use trovochat::{messages::{Fragment, Privmsg}, FromIrcMessage as _};
+    let input = "@emotes=25:10-14 :museun!museun@museun.tmi.trovo.tv PRIVMSG #museun :\u{2764} @shaken Kappa\r\n";
+    let msg = Privmsg::from_irc(trovochat::irc::parse_one(input).unwrap().1).unwrap();
+
+    assert_eq!(
+        msg.fragments(),
+        vec![
+            Fragment::Text("\u{2764} "),
+            Fragment::Mention("shaken"),
+            Fragment::Text(" "),
+            Fragment::Emote { id: 25, name: "Kappa" },
+        ]
+    );
+    ```
+    */
+    pub fn fragments(&'a self) -> Vec<Fragment<'a>> {
+        let emotes = self.tags().get("emotes").unwrap_or_default();
+        parse_fragments(self.data(), emotes, self.bits().is_some())
+    }
+
+    fn find_badge(&self, tag: &str, kind: BadgeKind<'_>) -> Option<Badge<'_>> {
+        self.tags()
+            .get(tag)
+            .into_iter()
+            .flat_map(parse_badges_iter)
+            .find(|x| x.kind == kind)
+    }
+
     fn contains_badge(&self, badge: BadgeKind<'_>) -> bool {
         self.tags()
             .get("badges")
@@ -431,4 +582,78 @@ mod tests {
             assert_eq!(msg.iter_emotes().count(), 2);
         }
     }
+
+    #[test]
+    fn privmsg_reply_parent() {
+        let input = "@display-name=shaken_bot;id=bbbbbbbb-bbbb-bbbb-bbbb-bbbbbbbbbbbb;reply-parent-display-name=Museun;reply-parent-msg-body=a\\sb\\:c;reply-parent-msg-id=aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa;reply-parent-user-id=23196011;reply-parent-user-login=museun :shaken_bot!shaken_bot@shaken_bot.tmi.trovo.tv PRIVMSG #museun :@museun hi\r\n";
+        for msg in parse(input).map(|s| s.unwrap()) {
+            let msg = Privmsg::from_irc(msg).unwrap();
+            let parent = msg.reply_parent().unwrap();
+            assert_eq!(parent.msg_id, "aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa");
+            assert_eq!(parent.user_id, Some(23196011));
+            assert_eq!(parent.user_login, "museun");
+            assert_eq!(parent.display_name, "Museun");
+            assert_eq!(parent.body, "a b;c");
+
+            assert_eq!(
+                parent.reply("#museun", "hello"),
+                crate::commands::reply("#museun", "aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa", "hello")
+            );
+            assert_eq!(
+                msg.reply_in_thread("hello"),
+                Some(crate::commands::reply(
+                    "#museun",
+                    "aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa",
+                    "hello"
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn privmsg_no_reply_parent() {
+        let input = "@id=bbbbbbbb-bbbb-bbbb-bbbb-bbbbbbbbbbbb :museun!museun@museun.tmi.trovo.tv PRIVMSG #museun :hello\r\n";
+        for msg in parse(input).map(|s| s.unwrap()) {
+            let msg = Privmsg::from_irc(msg).unwrap();
+            assert!(msg.reply_parent().is_none());
+            assert_eq!(
+                msg.reply_in_thread("hello"),
+                Some(crate::commands::reply(
+                    "#museun",
+                    "bbbbbbbb-bbbb-bbbb-bbbb-bbbbbbbbbbbb",
+                    "hello"
+                ))
+            );
+        }
+
+        let input = ":museun!museun@museun.tmi.trovo.tv PRIVMSG #museun :hello\r\n";
+        for msg in parse(input).map(|s| s.unwrap()) {
+            let msg = Privmsg::from_irc(msg).unwrap();
+            assert!(msg.reply_in_thread("hello").is_none());
+        }
+    }
+
+    #[test]
+    fn privmsg_badge_accessors() {
+        let input = "@badge-info=subscriber/14,founder/42;badges=subscriber/3012,founder/0,predictions/blue-2 :museun!museun@museun.tmi.trovo.tv PRIVMSG #museun :hello\r\n";
+        for msg in parse(input).map(|s| s.unwrap()) {
+            let msg = Privmsg::from_irc(msg).unwrap();
+            assert_eq!(msg.subscriber_months(), Some(14));
+            assert_eq!(msg.founder_number(), Some(42));
+            assert_eq!(msg.sub_tier(), Some(SubPlan::Tier3));
+
+            let prediction = msg.prediction().unwrap();
+            assert_eq!(prediction.color, "blue");
+            assert_eq!(prediction.outcome, 2);
+        }
+
+        let input = ":museun!museun@museun.tmi.trovo.tv PRIVMSG #museun :hello\r\n";
+        for msg in parse(input).map(|s| s.unwrap()) {
+            let msg = Privmsg::from_irc(msg).unwrap();
+            assert_eq!(msg.subscriber_months(), None);
+            assert_eq!(msg.founder_number(), None);
+            assert_eq!(msg.sub_tier(), None);
+            assert_eq!(msg.prediction(), None);
+        }
+    }
 }
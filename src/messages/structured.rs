@@ -0,0 +1,538 @@
+use super::*;
+use crate::{
+    irc::{IrcMessageBuilder, TagMap},
+    trovo::Emotes,
+    FromIrcMessage,
+};
+use serde::{
+    de::{Error as _, MapAccess, Visitor},
+    ser::{Error as _, SerializeMap as _},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::time::UNIX_EPOCH;
+
+/// The current version of the [Structured] schema
+pub const STRUCTURED_VERSION: u32 = 1;
+
+/**
+A message wrapper that serializes with named fields, rather than as the raw line
+
+The default serde representation of the types in [messages](super) is mostly the raw line. This is an opt-in
+representation for consumers (such as analytics pipelines) that want the fields.
+
+This works with every type in [messages](super), and with [Commands].
+
+# Schema (version 1)
+
+| field         | type                                            | description                                          |
+| ------------- | ----------------------------------------------- | ---------------------------------------------------- |
+| `version`     | number                                          | the schema version, see [STRUCTURED_VERSION]         |
+| `kind`        | string                                          | the kind of message, e.g. `Privmsg` or `Raw`         |
+| `raw`         | string                                          | the raw IRC line                                     |
+| `command`     | string                                          | the IRC command, e.g. `PRIVMSG`                      |
+| `prefix`      | string or null                                  | the IRC prefix, e.g. `museun!museun@museun.tmi.trovo.tv` |
+| `args`        | array of strings                                | the IRC arguments                                    |
+| `data`        | string or null                                  | the trailing data                                    |
+| `tags`        | object of strings                               | the tags, with their values unescaped                |
+| `channel`     | string or null                                  | see [HasChannel]                                     |
+| `user`        | object or null                                  | see [HasSender]: `login`, `display_name`, `id` and `color` (as `#RRGGBB`) |
+| `badges`      | array of `{ "name", "version" }`                | from the `badges` tag                                |
+| `badge_info`  | array of `{ "name", "version" }`                | from the `badge-info` tag                            |
+| `emotes`      | array of `{ "id", "ranges": [[start, end]] }`   | from the `emotes` tag                                |
+| `server_time` | number or null                                  | milliseconds since the unix epoch, see [Timestamped] |
+
+When deserializing, only `version` and either `raw` or `command` are required.
+If `raw` is missing, the line is rebuilt from `command`, `prefix`, `args`, `data` and `tags`. The other fields are ignored.
+
+A [Commands::Custom] message serializes with the `Custom` kind, but the `kind` is ignored when deserializing. It
+comes back as whatever the line parses as, usually [Commands::Raw]. The custom message's own type is not known here.
+
+```
+# use trovochat::{messages::{Privmsg, Structured}, FromIrcMessage as _};
+let input = "@badges=subscriber/6;display-name=Museun;user-id=23196011 :museun!museun@museun.tmi.trovo.tv PRIVMSG #museun :hello\r\n";
+let pm = Privmsg::from_irc(trovochat::irc::parse_one(input).unwrap().1).unwrap();
+
+let json = serde_json::to_value(&Structured(&pm)).unwrap();
+assert_eq!(json["version"], 1);
+assert_eq!(json["kind"], "Privmsg");
+assert_eq!(json["channel"], "#museun");
+assert_eq!(json["user"]["login"], "museun");
+assert_eq!(json["user"]["id"], 23196011);
+assert_eq!(json["badges"][0]["name"], "subscriber");
+assert_eq!(json["data"], "hello");
+
+let Structured(right) = serde_json::from_value::<Structured<Privmsg>>(json).unwrap();
+assert_eq!(pm, right);
+```
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct Structured<T>(pub T);
+
+impl<T> Structured<T> {
+    /// Get the message back
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+/// A message that can be serialized with [Structured]
+pub trait StructuredMessage {
+    /// The kind of message, e.g. `Privmsg`
+    fn kind(&self) -> &'static str;
+
+    /// The raw IRC line
+    fn raw_line(&self) -> &str;
+}
+
+impl<T> StructuredMessage for &T
+where
+    T: StructuredMessage + ?Sized,
+{
+    fn kind(&self) -> &'static str {
+        (**self).kind()
+    }
+
+    fn raw_line(&self) -> &str {
+        (**self).raw_line()
+    }
+}
+
+macro_rules! structured {
+    ($($ty:ident)*) => {
+        $(impl<'a> StructuredMessage for $ty<'a> {
+            fn kind(&self) -> &'static str {
+                stringify!($ty)
+            }
+
+            fn raw_line(&self) -> &str {
+                self.raw()
+            }
+        })*
+    };
+}
+
+structured! {
+    IrcReady
+    Ready
+    Cap
+    ClearChat
+    ClearMsg
+    GlobalUserState
+    HostTarget
+    Join
+    Notice
+    Part
+    Ping
+    Pong
+    Privmsg
+    Reconnect
+    RoomState
+    UserNotice
+    UserState
+    Whisper
+    Welcome
+    Motd
+    Names
+    EndOfNames
+    UnknownCommand
+}
+
+impl<'a> StructuredMessage for Commands<'a> {
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Raw(..) => "Raw",
+            Self::IrcReady(msg) => StructuredMessage::kind(msg),
+            Self::Ready(msg) => StructuredMessage::kind(msg),
+            Self::Cap(msg) => StructuredMessage::kind(msg),
+            Self::ClearChat(msg) => StructuredMessage::kind(msg),
+            Self::ClearMsg(msg) => StructuredMessage::kind(msg),
+            Self::GlobalUserState(msg) => StructuredMessage::kind(msg),
+            Self::HostTarget(msg) => StructuredMessage::kind(msg),
+            Self::Join(msg) => StructuredMessage::kind(msg),
+            Self::Notice(msg) => StructuredMessage::kind(msg),
+            Self::Part(msg) => StructuredMessage::kind(msg),
+            Self::Ping(msg) => StructuredMessage::kind(msg),
+            Self::Pong(msg) => StructuredMessage::kind(msg),
+            Self::Privmsg(msg) => StructuredMessage::kind(msg),
+            Self::Reconnect(msg) => StructuredMessage::kind(msg),
+            Self::RoomState(msg) => StructuredMessage::kind(msg),
+            Self::UserNotice(msg) => StructuredMessage::kind(msg),
+            Self::UserState(msg) => StructuredMessage::kind(msg),
+            Self::Whisper(msg) => StructuredMessage::kind(msg),
+            Self::Welcome(msg) => StructuredMessage::kind(msg),
+            Self::Motd(msg) => StructuredMessage::kind(msg),
+            Self::Names(msg) => StructuredMessage::kind(msg),
+            Self::EndOfNames(msg) => StructuredMessage::kind(msg),
+            Self::UnknownCommand(msg) => StructuredMessage::kind(msg),
+            Self::Custom(..) => "Custom",
+        }
+    }
+
+    fn raw_line(&self) -> &str {
+        self.raw()
+    }
+}
+
+impl<T> Serialize for Structured<T>
+where
+    T: StructuredMessage,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let raw = self.0.raw_line();
+        let (_, msg) = crate::irc::parse_one(raw).map_err(S::Error::custom)?;
+
+        let mut map = serializer.serialize_map(Some(14))?;
+        map.serialize_entry("version", &STRUCTURED_VERSION)?;
+        map.serialize_entry("kind", self.0.kind())?;
+        map.serialize_entry("raw", raw)?;
+        map.serialize_entry("command", msg.get_command())?;
+        map.serialize_entry("prefix", &full_prefix(raw))?;
+        map.serialize_entry("args", &SplitArgs(msg.get_args()))?;
+        map.serialize_entry("data", &msg.get_data())?;
+        map.serialize_entry("tags", &UnescapedTags(msg.get_tags()))?;
+
+        // the typed view, which borrows from the raw line
+        let tags = msg.get_tags().map(TagMap::parse).unwrap_or_default();
+        let cmd = Commands::from_irc(msg).map_err(S::Error::custom)?;
+
+        map.serialize_entry("channel", &cmd.channel())?;
+        map.serialize_entry("user", &cmd.sender().map(User))?;
+        map.serialize_entry("badges", &BadgeList(tags.get("badges")))?;
+        map.serialize_entry("badge_info", &BadgeList(tags.get("badge-info")))?;
+        map.serialize_entry(
+            "emotes",
+            &EmoteList(
+                tags.get("emotes")
+                    .map(|emotes| Emotes::parse(emotes).collect()),
+            ),
+        )?;
+        map.serialize_entry(
+            "server_time",
+            &cmd.server_time()
+                .and_then(|ts| ts.duration_since(UNIX_EPOCH).ok())
+                .map(|ts| ts.as_millis() as u64),
+        )?;
+        map.end()
+    }
+}
+
+// the parsed prefix only keeps the nick (or the host), so this takes all of it from the line
+fn full_prefix(raw: &str) -> Option<&str> {
+    let line = match raw.strip_prefix('@') {
+        Some(tail) => &tail[tail.find(' ')? + 1..],
+        None => raw,
+    };
+    line.strip_prefix(':')?.split(' ').next()
+}
+
+struct SplitArgs<'a>(Option<&'a str>);
+
+impl<'a> Serialize for SplitArgs<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let args = self
+            .0
+            .into_iter()
+            .flat_map(|args| args.split_whitespace())
+            .collect::<Vec<_>>();
+        serializer.collect_seq(args)
+    }
+}
+
+struct UnescapedTags<'a>(Option<&'a str>);
+
+impl<'a> Serialize for UnescapedTags<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let tags = self.0.map(TagMap::parse).unwrap_or_default();
+        serializer.collect_map(tags.iter())
+    }
+}
+
+struct User<'a>(&'a dyn HasSender);
+
+impl<'a> Serialize for User<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(4))?;
+        map.serialize_entry("login", &self.0.login())?;
+        map.serialize_entry("display_name", &self.0.display_name())?;
+        map.serialize_entry("id", &self.0.user_id())?;
+        map.serialize_entry("color", &self.0.color().map(|color| color.rgb.to_string()))?;
+        map.end()
+    }
+}
+
+struct BadgeList<'a>(Option<&'a str>);
+
+impl<'a> Serialize for BadgeList<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        struct Badge<'a> {
+            name: &'a str,
+            version: &'a str,
+        }
+
+        let badges = self
+            .0
+            .into_iter()
+            .flat_map(|badges| badges.split(','))
+            .filter_map(|badge| {
+                let mut iter = badge.splitn(2, '/');
+                Some(Badge {
+                    name: iter.next().filter(|s| !s.is_empty())?,
+                    version: iter.next()?,
+                })
+            })
+            .collect::<Vec<_>>();
+        serializer.collect_seq(badges)
+    }
+}
+
+struct EmoteList(Option<Vec<Emotes>>);
+
+impl Serialize for EmoteList {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        struct Emote {
+            id: usize,
+            ranges: Vec<(u16, u16)>,
+        }
+
+        let emotes = self
+            .0
+            .iter()
+            .flatten()
+            .map(|emote| Emote {
+                id: emote.id,
+                ranges: emote
+                    .ranges
+                    .iter()
+                    .map(|range| (range.start, range.end))
+                    .collect(),
+            })
+            .collect::<Vec<_>>();
+        serializer.collect_seq(emotes)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Structured<T>
+where
+    T: FromIrcMessage<'static>,
+    T::Error: std::fmt::Display,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Input {
+            version: u32,
+            raw: Option<String>,
+            command: Option<String>,
+            prefix: Option<String>,
+            #[serde(default)]
+            args: Vec<String>,
+            data: Option<String>,
+            #[serde(default)]
+            tags: OrderedTags,
+        }
+
+        let input = Input::deserialize(deserializer)?;
+        if input.version > STRUCTURED_VERSION {
+            return Err(D::Error::custom(format!(
+                "unsupported schema version: {} (the newest supported is {})",
+                input.version, STRUCTURED_VERSION
+            )));
+        }
+
+        let msg = match (input.raw, input.command) {
+            (Some(raw), _) => crate::irc::IrcMessage::parse(crate::MaybeOwned::Shared(raw.into()))
+                .map_err(D::Error::custom)?,
+            (None, Some(command)) => {
+                let mut builder = IrcMessageBuilder::new(command).tags(input.tags.0);
+                if let Some(prefix) = input.prefix {
+                    builder = builder.prefix(prefix);
+                }
+                for arg in input.args {
+                    builder = builder.arg(arg);
+                }
+                if let Some(data) = input.data {
+                    builder = builder.data(data);
+                }
+                builder.build().map_err(D::Error::custom)?
+            }
+            (None, None) => return Err(D::Error::missing_field("raw")),
+        };
+
+        T::from_irc(msg).map(Self).map_err(|err| {
+            D::Error::custom(format!(
+                "cannot parse '{}' because: {}",
+                std::any::type_name::<T>(),
+                err
+            ))
+        })
+    }
+}
+
+// keeps the tags in the order they were written
+#[derive(Default)]
+struct OrderedTags(TagMap);
+
+impl<'de> Deserialize<'de> for OrderedTags {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct TagsVisitor;
+
+        impl<'de> Visitor<'de> for TagsVisitor {
+            type Value = OrderedTags;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(formatter, "a map of tags")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+            where
+                A: MapAccess<'de>,
+            {
+                let mut tags = TagMap::new();
+                while let Some((key, value)) = map.next_entry::<String, String>()? {
+                    tags.insert(key, value);
+                }
+                Ok(OrderedTags(tags))
+            }
+        }
+
+        deserializer.deserialize_map(TagsVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IntoOwned as _;
+
+    const INPUT: &str = "@badge-info=subscriber/8;badges=broadcaster/1,subscriber/6;color=#FF0000;display-name=Museun;emotes=25:0-4,6-10;tmi-sent-ts=1601079032426;user-id=23196011;x-msg=a\\sb :museun!museun@museun.tmi.trovo.tv PRIVMSG #museun :Kappa Kappa\r\n";
+
+    fn commands(input: &str) -> Commands<'static> {
+        Commands::from_irc(crate::irc::parse_one(input).unwrap().1)
+            .unwrap()
+            .into_owned()
+    }
+
+    #[test]
+    fn structured_shape() {
+        let json = serde_json::to_value(Structured(commands(INPUT))).unwrap();
+
+        let expected = serde_json::json!({
+            "version": 1,
+            "kind": "Privmsg",
+            "raw": INPUT,
+            "command": "PRIVMSG",
+            "prefix": "museun!museun@museun.tmi.trovo.tv",
+            "args": ["#museun"],
+            "data": "Kappa Kappa",
+            "tags": {
+                "badge-info": "subscriber/8",
+                "badges": "broadcaster/1,subscriber/6",
+                "color": "#FF0000",
+                "display-name": "Museun",
+                "emotes": "25:0-4,6-10",
+                "tmi-sent-ts": "1601079032426",
+                "user-id": "23196011",
+                "x-msg": "a b",
+            },
+            "channel": "#museun",
+            "user": {
+                "login": "museun",
+                "display_name": "Museun",
+                "id": 23196011,
+                "color": "#FF0000",
+            },
+            "badges": [
+                { "name": "broadcaster", "version": "1" },
+                { "name": "subscriber", "version": "6" },
+            ],
+            "badge_info": [
+                { "name": "subscriber", "version": "8" },
+            ],
+            "emotes": [
+                { "id": 25, "ranges": [[0, 4], [6, 10]] },
+            ],
+            "server_time": 1601079032426_u64,
+        });
+
+        assert_eq!(json, expected);
+    }
+
+    #[test]
+    fn structured_round_trip() {
+        let inputs = &[
+            INPUT,
+            ":museun!museun@museun.tmi.trovo.tv JOIN #museun\r\n",
+            "PING :1234\r\n",
+            "@login=museun;target-msg-id=abc :tmi.trovo.tv CLEARMSG #museun :hello\r\n",
+            ":tmi.trovo.tv FOOBAR #museun :hello\r\n",
+        ];
+
+        for input in inputs {
+            let left = commands(input);
+
+            let json = serde_json::to_string(&Structured(&left)).unwrap();
+            let Structured(right) = serde_json::from_str::<Structured<Commands>>(&json).unwrap();
+            assert_eq!(left, right);
+
+            let vec = rmp_serde::to_vec_named(&Structured(&left)).unwrap();
+            let Structured(right) = rmp_serde::from_slice::<Structured<Commands>>(&vec).unwrap();
+            assert_eq!(left, right);
+        }
+    }
+
+    #[test]
+    fn structured_without_raw() {
+        let json = serde_json::json!({
+            "version": 1,
+            "command": "PRIVMSG",
+            "prefix": "museun!museun@museun.tmi.trovo.tv",
+            "args": ["#museun"],
+            "data": "hello world",
+            "tags": { "display-name": "Museun", "x-msg": "a b" },
+            "channel": "this is ignored",
+        });
+
+        let Structured(pm) = serde_json::from_value::<Structured<Privmsg>>(json).unwrap();
+        assert_eq!(
+            pm.raw(),
+            "@display-name=Museun;x-msg=a\\sb :museun!museun@museun.tmi.trovo.tv PRIVMSG #museun :hello world\r\n"
+        );
+        assert_eq!(pm.channel(), "#museun");
+        assert_eq!(pm.data(), "hello world");
+    }
+
+    #[test]
+    fn structured_version() {
+        let json = serde_json::json!({ "version": 2, "raw": "PING :1234\r\n" });
+        let err = serde_json::from_value::<Structured<Ping>>(json).unwrap_err();
+        assert!(err.to_string().contains("unsupported schema version"));
+
+        let json = serde_json::json!({ "version": 1 });
+        assert!(serde_json::from_value::<Structured<Ping>>(json).is_err());
+    }
+}
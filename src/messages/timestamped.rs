@@ -0,0 +1,239 @@
+use super::*;
+use crate::IrcMessage;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/**
+Timestamps attached to a message
+
+The server time comes from the `tmi-sent-ts` tag, so it is only available on messages that have tags (and only if the `Tags` capability was requested).
+
+The local receive time is only available on a [Received] message, which the [AsyncRunner](crate::AsyncRunner) can produce.
+
+```
+# use trovochat::{messages::{Privmsg, Timestamped}, FromIrcMessage as _};
+# use std::time::{Duration, UNIX_EPOCH};
+let input = "@tmi-sent-ts=1601079032426 :museun!museun@museun.tmi.trovo.tv PRIVMSG #museun :hello\r\n";
+let msg = Privmsg::from_irc(trovochat::irc::parse_one(input).unwrap().1).unwrap();
+
+assert_eq!(
+    msg.server_time(),
+    Some(UNIX_EPOCH + Duration::from_millis(1601079032426))
+);
+assert_eq!(msg.received_time(), None);
+```
+*/
+pub trait Timestamped {
+    /// When Trovo received this message, if it was attached
+    fn server_time(&self) -> Option<SystemTime>;
+
+    /// When this message was received locally, if it was recorded
+    fn received_time(&self) -> Option<SystemTime> {
+        None
+    }
+
+    /// How long it took between Trovo receiving this message and it being received locally
+    ///
+    /// This requires both timestamps, and is subject to clock skew between the server and this machine.
+    fn latency(&self) -> Option<Duration> {
+        self.received_time()?
+            .duration_since(self.server_time()?)
+            .ok()
+    }
+}
+
+/// A message along with the local time it was received
+#[derive(Debug, Clone, PartialEq)]
+pub struct Received<T> {
+    message: T,
+    received: SystemTime,
+}
+
+impl<T> Received<T> {
+    /// Attach the current time to this message
+    pub fn new(message: T) -> Self {
+        Self::with_time(message, SystemTime::now())
+    }
+
+    /// Attach this time to this message
+    pub fn with_time(message: T, received: SystemTime) -> Self {
+        Self { message, received }
+    }
+
+    /// Get the message
+    pub fn message(&self) -> &T {
+        &self.message
+    }
+
+    /// The time this message was received locally
+    pub fn received(&self) -> SystemTime {
+        self.received
+    }
+
+    /// Get the message back, discarding the time
+    pub fn into_inner(self) -> T {
+        self.message
+    }
+}
+
+impl<T> Timestamped for Received<T>
+where
+    T: Timestamped,
+{
+    fn server_time(&self) -> Option<SystemTime> {
+        self.message.server_time()
+    }
+
+    fn received_time(&self) -> Option<SystemTime> {
+        Some(self.received)
+    }
+}
+
+const SENT_TS: &str = "tmi-sent-ts";
+
+fn from_millis(ts: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_millis(ts)
+}
+
+macro_rules! timestamped {
+    (@tags $($ty:ident)*) => {
+        $(impl<'a> Timestamped for $ty<'a> {
+            fn server_time(&self) -> Option<SystemTime> {
+                self.tags().get_parsed(SENT_TS).map(from_millis)
+            }
+        })*
+    };
+
+    (@none $($ty:ident)*) => {
+        $(
+            /// These messages don't have tags, so this is always None
+            impl<'a> Timestamped for $ty<'a> {
+                fn server_time(&self) -> Option<SystemTime> {
+                    None
+                }
+            }
+        )*
+    };
+}
+
+timestamped! {
+    @tags
+    ClearChat
+    ClearMsg
+    GlobalUserState
+    Notice
+    Privmsg
+    RoomState
+    UserNotice
+    UserState
+    Whisper
+}
+
+timestamped! {
+    @none
+    Cap
+    EndOfNames
+    HostTarget
+    IrcReady
+    Join
+    Motd
+    Names
+    Part
+    Ping
+    Pong
+    Ready
+    Reconnect
+    UnknownCommand
+    Welcome
+}
+
+impl<'a> Timestamped for IrcMessage<'a> {
+    fn server_time(&self) -> Option<SystemTime> {
+        self.get_tags()?
+            .trim_start_matches('@')
+            .split(';')
+            .filter_map(|tag| {
+                let mut iter = tag.splitn(2, '=');
+                Some((iter.next()?, iter.next()?))
+            })
+            .find(|&(key, _)| key == SENT_TS)
+            .and_then(|(_, val)| val.parse().ok())
+            .map(from_millis)
+    }
+}
+
+impl<'a> Timestamped for Commands<'a> {
+    fn server_time(&self) -> Option<SystemTime> {
+        match self {
+            Self::Raw(msg) => msg.server_time(),
+            Self::IrcReady(msg) => msg.server_time(),
+            Self::Ready(msg) => msg.server_time(),
+            Self::Cap(msg) => msg.server_time(),
+            Self::ClearChat(msg) => msg.server_time(),
+            Self::ClearMsg(msg) => msg.server_time(),
+            Self::GlobalUserState(msg) => msg.server_time(),
+            Self::HostTarget(msg) => msg.server_time(),
+            Self::Join(msg) => msg.server_time(),
+            Self::Notice(msg) => msg.server_time(),
+            Self::Part(msg) => msg.server_time(),
+            Self::Ping(msg) => msg.server_time(),
+            Self::Pong(msg) => msg.server_time(),
+            Self::Privmsg(msg) => msg.server_time(),
+            Self::Reconnect(msg) => msg.server_time(),
+            Self::RoomState(msg) => msg.server_time(),
+            Self::UserNotice(msg) => msg.server_time(),
+            Self::UserState(msg) => msg.server_time(),
+            Self::Whisper(msg) => msg.server_time(),
+            Self::Welcome(msg) => msg.server_time(),
+            Self::Motd(msg) => msg.server_time(),
+            Self::Names(msg) => msg.server_time(),
+            Self::EndOfNames(msg) => msg.server_time(),
+            Self::UnknownCommand(msg) => msg.server_time(),
+            Self::Custom(msg) => crate::irc::parse_one(msg.raw()).ok()?.1.server_time(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FromIrcMessage as _;
+
+    #[test]
+    fn server_time() {
+        let input = "@tmi-sent-ts=1601079032426 :museun!museun@museun.tmi.trovo.tv PRIVMSG #museun :hello\r\n\
+                     @login=museun;tmi-sent-ts=1601079032426 :tmi.trovo.tv CLEARMSG #museun :hello\r\n\
+                     @a=b;tmi-sent-ts=1601079032426 :tmi.trovo.tv FOOBAR #museun\r\n\
+                     :museun!museun@museun.tmi.trovo.tv JOIN #museun\r\n\
+                     :museun!museun@museun.tmi.trovo.tv PRIVMSG #museun :hello\r\n";
+
+        let expected = UNIX_EPOCH + Duration::from_millis(1_601_079_032_426);
+
+        let times = crate::irc::parse(input)
+            .map(|msg| Commands::from_irc(msg.unwrap()).unwrap())
+            .map(|msg| msg.server_time())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            times,
+            vec![Some(expected), Some(expected), Some(expected), None, None]
+        );
+    }
+
+    #[test]
+    fn received() {
+        let input =
+            "@tmi-sent-ts=1000 :museun!museun@museun.tmi.trovo.tv PRIVMSG #museun :hello\r\n";
+        let msg = Commands::from_irc(crate::irc::parse_one(input).unwrap().1).unwrap();
+
+        let received = UNIX_EPOCH + Duration::from_millis(1250);
+        let msg = Received::with_time(msg, received);
+
+        assert_eq!(msg.received_time(), Some(received));
+        assert_eq!(
+            msg.server_time(),
+            Some(UNIX_EPOCH + Duration::from_millis(1000))
+        );
+        assert_eq!(msg.latency(), Some(Duration::from_millis(250)));
+        assert!(matches!(msg.into_inner(), Commands::Privmsg { .. }));
+    }
+}
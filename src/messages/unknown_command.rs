@@ -0,0 +1,105 @@
+use crate::{irc::*, MaybeOwned, MaybeOwnedIndex, Validator};
+
+/// The server didn't understand a command that you sent -- `421`
+///
+/// This usually means a malformed or unsupported command was written to the connection.
+#[derive(Clone, PartialEq)]
+pub struct UnknownCommand<'a> {
+    raw: MaybeOwned<'a>,
+    username: MaybeOwnedIndex,
+    command: MaybeOwnedIndex,
+    data: Option<MaybeOwnedIndex>,
+}
+
+impl<'a> UnknownCommand<'a> {
+    raw!();
+    str_field!(
+        /// The name the server will refer to you as
+        username
+    );
+    str_field!(
+        /// The command that the server didn't understand
+        command
+    );
+    opt_str_field!(
+        /// The reason the server gave, e.g. `Unknown command`
+        data
+    );
+}
+
+impl<'a> std::fmt::Display for UnknownCommand<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "the server rejected the '{}' command: {}",
+            self.command(),
+            self.data().unwrap_or("Unknown command")
+        )
+    }
+}
+
+impl<'a> FromIrcMessage<'a> for UnknownCommand<'a> {
+    type Error = MessageError;
+
+    fn from_irc(msg: IrcMessage<'a>) -> Result<Self, Self::Error> {
+        msg.expect_command(IrcMessage::UNKNOWN_COMMAND)?;
+
+        let this = Self {
+            username: msg.expect_arg_index(0)?,
+            command: msg.expect_arg_index(1)?,
+            data: msg.data,
+            raw: msg.raw,
+        };
+
+        Ok(this)
+    }
+
+    into_inner_raw!();
+}
+
+into_owned!(UnknownCommand {
+    raw,
+    username,
+    command,
+    data
+});
+impl_custom_debug!(UnknownCommand {
+    raw,
+    username,
+    command,
+    data
+});
+serde_struct!(UnknownCommand {
+    raw,
+    username,
+    command,
+    data
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn unknown_command_serde() {
+        let input = ":tmi.trovo.tv 421 shaken_bot WHO :Unknown command\r\n";
+        crate::serde::round_trip_json::<UnknownCommand>(input);
+        crate::serde::round_trip_rmp::<UnknownCommand>(input);
+    }
+
+    #[test]
+    fn unknown_command() {
+        let input = ":tmi.trovo.tv 421 shaken_bot WHO :Unknown command\r\n";
+        for msg in parse(input).map(|s| s.unwrap()) {
+            let msg = UnknownCommand::from_irc(msg).unwrap();
+            assert_eq!(msg.username(), "shaken_bot");
+            assert_eq!(msg.command(), "WHO");
+            assert_eq!(msg.data().unwrap(), "Unknown command");
+            assert_eq!(
+                msg.to_string(),
+                "the server rejected the 'WHO' command: Unknown command"
+            );
+        }
+    }
+}
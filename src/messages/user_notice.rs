@@ -1,3 +1,4 @@
+use super::UserNoticeEvent;
 use crate::trovo::{parse_badges, parse_emotes, Badge, BadgeInfo, Color, Emotes};
 use crate::{irc::*, MaybeOwned, MaybeOwnedIndex, Validator};
 
@@ -18,6 +19,18 @@ pub enum SubPlan<'a> {
     Unknown(&'a str),
 }
 
+impl<'a> SubPlan<'a> {
+    pub(super) fn parse(plan: &'a str) -> Self {
+        match plan {
+            "Prime" => Self::Prime,
+            "1000" | "Tier1" => Self::Tier1,
+            "2000" | "Tier2" => Self::Tier2,
+            "3000" | "Tier3" => Self::Tier3,
+            plan => Self::Unknown(plan),
+        }
+    }
+}
+
 /// The kind of notice it was, retrieved via [UserNotice::msg_id()]
 #[non_exhaustive]
 #[derive(Clone, Debug, PartialEq, Hash)]
@@ -146,6 +159,27 @@ impl<'a> UserNotice<'a> {
         .into()
     }
 
+    /// The typed event for this notice, with all of its parameters
+    ///
+    /// This returns an error if the `msg-id` tag, or a parameter required by that kind of notice, is missing or cannot be parsed.
+    ///
+    /// ```
+    /// # use trovochat::{messages::{UserNotice, UserNoticeEvent}, FromIrcMessage as _};
+    /// let input = "@msg-id=raid;msg-param-login=museun;msg-param-displayName=Museun;msg-param-viewerCount=42 :tmi.trovo.tv USERNOTICE #shaken_bot\r\n";
+    /// let msg = UserNotice::from_irc(trovochat::irc::parse_one(input).unwrap().1).unwrap();
+    ///
+    /// match msg.event().unwrap() {
+    ///     UserNoticeEvent::Raid(raid) => {
+    ///         assert_eq!(raid.source, "museun");
+    ///         assert_eq!(raid.viewer_count, 42);
+    ///     }
+    ///     _ => unreachable!(),
+    /// }
+    /// ```
+    pub fn event(&'a self) -> Result<UserNoticeEvent<'a>, MessageError> {
+        UserNoticeEvent::from_tags(self.tags())
+    }
+
     /// The id of the room for this notice
     pub fn room_id(&self) -> Option<u64> {
         self.tags().get_parsed("room-id")
@@ -266,16 +300,7 @@ impl<'a> UserNotice<'a> {
     /// 3000 refer to the first, second, and third levels of paid subscriptions,
     /// respectively (currently $4.99, $9.99, and $24.99).
     pub fn msg_param_sub_plan(&'a self) -> Option<SubPlan<'a>> {
-        self.tags().get("msg-param-sub-plan").and_then(|s| {
-            match s {
-                "Prime" => SubPlan::Prime,
-                "Tier1" => SubPlan::Tier1,
-                "Tier2" => SubPlan::Tier2,
-                "Tier3" => SubPlan::Tier3,
-                s => SubPlan::Unknown(s),
-            }
-            .into()
-        })
+        self.tags().get("msg-param-sub-plan").map(SubPlan::parse)
     }
 
     /// (Sent only on sub, resub, subgift, anonsubgift) The display name of the
@@ -398,4 +423,107 @@ mod tests {
             assert_eq!(msg.tags().is_empty(), false);
         }
     }
+
+    #[test]
+    fn user_notice_event() {
+        use crate::messages::{SubGift, Subscription};
+
+        let input = "@login=lllairjordanlll;msg-id=resub;msg-param-cumulative-months=8;msg-param-should-share-streak=1;msg-param-streak-months=3;msg-param-sub-plan=1000 :tmi.trovo.tv USERNOTICE #giantwaffle\r\n\
+                     @login=museun;msg-id=subgift;msg-param-months=2;msg-param-recipient-display-name=Shaken_Bot;msg-param-recipient-id=12345;msg-param-recipient-user-name=shaken_bot;msg-param-sub-plan=Prime :tmi.trovo.tv USERNOTICE #museun\r\n\
+                     @login=museun;msg-id=submysterygift;msg-param-mass-gift-count=5;msg-param-sender-count=0;msg-param-sub-plan=2000 :tmi.trovo.tv USERNOTICE #museun\r\n\
+                     @msg-id=bitsbadgetier;msg-param-threshold=1000 :tmi.trovo.tv USERNOTICE #museun\r\n\
+                     @msg-id=something-new :tmi.trovo.tv USERNOTICE #museun\r\n";
+
+        let msgs = parse(input)
+            .map(|msg| UserNotice::from_irc(msg.unwrap()).unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            msgs[0].event().unwrap(),
+            UserNoticeEvent::Resub(Subscription {
+                months: 8,
+                streak: Some(3),
+                plan: SubPlan::Tier1,
+            })
+        );
+
+        assert_eq!(
+            msgs[1].event().unwrap(),
+            UserNoticeEvent::SubGift(SubGift {
+                recipient_id: 12345,
+                recipient_login: "shaken_bot",
+                recipient_display_name: "Shaken_Bot".into(),
+                gifter: Some("museun"),
+                months: 2,
+                plan: SubPlan::Prime,
+            })
+        );
+
+        match msgs[2].event().unwrap() {
+            UserNoticeEvent::SubMysteryGift(gift) => {
+                assert_eq!(gift.gifter, Some("museun"));
+                assert_eq!(gift.count, 5);
+                assert_eq!(gift.total, None);
+                assert_eq!(gift.plan, SubPlan::Tier2);
+            }
+            event => panic!("unexpected event: {:?}", event),
+        }
+
+        match msgs[3].event().unwrap() {
+            UserNoticeEvent::BitsBadgeTier(tier) => assert_eq!(tier.threshold, 1000),
+            event => panic!("unexpected event: {:?}", event),
+        }
+
+        assert_eq!(
+            msgs[4].event().unwrap(),
+            UserNoticeEvent::Unknown("something-new")
+        );
+    }
+
+    #[test]
+    fn user_notice_event_unescaped() {
+        let input = "@login=museun;msg-id=giftpaidupgrade;msg-param-promo-gift-total=3;msg-param-promo-name=Subtember\\s2018;msg-param-sender-login=shaken_bot :tmi.trovo.tv USERNOTICE #museun\r\n\
+                     @msg-id=raid;msg-param-displayName=Some\\sChannel;msg-param-login=somechannel;msg-param-viewerCount=10 :tmi.trovo.tv USERNOTICE #museun\r\n";
+
+        let msgs = parse(input)
+            .map(|msg| UserNotice::from_irc(msg.unwrap()).unwrap())
+            .collect::<Vec<_>>();
+
+        match msgs[0].event().unwrap() {
+            UserNoticeEvent::GiftPaidUpgrade(upgrade) => {
+                assert_eq!(upgrade.promo_name.unwrap(), "Subtember 2018");
+                assert_eq!(upgrade.promo_gift_total, Some(3));
+            }
+            event => panic!("unexpected event: {:?}", event),
+        }
+
+        match msgs[1].event().unwrap() {
+            UserNoticeEvent::Raid(raid) => assert_eq!(raid.source_display_name, "Some Channel"),
+            event => panic!("unexpected event: {:?}", event),
+        }
+    }
+
+    #[test]
+    fn user_notice_event_missing_params() {
+        let input = "@msg-id=raid;msg-param-login=museun;msg-param-viewerCount=10 :tmi.trovo.tv USERNOTICE #museun\r\n\
+                     @msg-id=sub;msg-param-cumulative-months=abc;msg-param-sub-plan=1000 :tmi.trovo.tv USERNOTICE #museun\r\n\
+                     :tmi.trovo.tv USERNOTICE #museun\r\n";
+
+        let msgs = parse(input)
+            .map(|msg| UserNotice::from_irc(msg.unwrap()).unwrap())
+            .collect::<Vec<_>>();
+
+        assert!(matches!(
+            msgs[0].event().unwrap_err(),
+            MessageError::ExpectedTag { name } if name == "msg-param-displayName"
+        ));
+        assert!(matches!(
+            msgs[1].event().unwrap_err(),
+            MessageError::CannotParseTag { name, .. } if name == "msg-param-cumulative-months"
+        ));
+        assert!(matches!(
+            msgs[2].event().unwrap_err(),
+            MessageError::ExpectedTag { name } if name == "msg-id"
+        ));
+    }
 }
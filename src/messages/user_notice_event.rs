@@ -0,0 +1,254 @@
+use super::SubPlan;
+use crate::{irc::Tags, MaybeOwned, MessageError};
+use std::str::FromStr;
+
+/// A typed event from a [UserNotice](super::UserNotice), retrieved via [UserNotice::event()](super::UserNotice::event)
+///
+/// Each variant contains the parameters that Trovo sends for that kind of notice.
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum UserNoticeEvent<'a> {
+    /// A user subscribed to the channel
+    Sub(Subscription<'a>),
+    /// A user re-subscribed to the channel
+    Resub(Subscription<'a>),
+    /// A user gifted a subscription to another user
+    SubGift(SubGift<'a>),
+    /// An anonymous user gifted a subscription to another user
+    AnonSubGift(SubGift<'a>),
+    /// A user is gifting several subscriptions to the channel
+    ///
+    /// This is followed by a [SubGift](UserNoticeEvent::SubGift) for each of the gifts
+    SubMysteryGift(SubMysteryGift<'a>),
+    /// A user continued a subscription that was gifted to them
+    GiftPaidUpgrade(GiftPaidUpgrade<'a>),
+    /// A user continued a subscription that was gifted to them by an anonymous user
+    AnonGiftPaidUpgrade(GiftPaidUpgrade<'a>),
+    /// A reward gift was given
+    RewardGift,
+    /// Another channel is raiding this channel
+    Raid(Raid<'a>),
+    /// A raid was canceled
+    Unraid,
+    /// A ritual, such as a new chatter
+    Ritual(Ritual<'a>),
+    /// A user earned a new tier of the bits badge
+    BitsBadgeTier(BitsBadgeTier),
+    /// An unknown notice type (a catch-all)
+    Unknown(&'a str),
+}
+
+/// A subscription or re-subscription
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Subscription<'a> {
+    /// The total number of months the user has subscribed
+    pub months: u64,
+    /// The number of consecutive months the user has subscribed, if they chose to share it
+    pub streak: Option<u64>,
+    /// The subscription plan
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    pub plan: SubPlan<'a>,
+}
+
+/// A gifted subscription
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct SubGift<'a> {
+    /// The user id of the recipient
+    pub recipient_id: u64,
+    /// The login of the recipient
+    pub recipient_login: &'a str,
+    /// The (unescaped) display name of the recipient
+    pub recipient_display_name: MaybeOwned<'a>,
+    /// The login of the gifter, if they weren't anonymous
+    pub gifter: Option<&'a str>,
+    /// The total number of months the recipient has subscribed
+    pub months: u64,
+    /// The subscription plan
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    pub plan: SubPlan<'a>,
+}
+
+/// A batch of gifted subscriptions
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct SubMysteryGift<'a> {
+    /// The login of the gifter
+    pub gifter: Option<&'a str>,
+    /// How many subscriptions are being gifted
+    pub count: u64,
+    /// How many subscriptions the gifter has gifted to this channel in total, if they shared it
+    pub total: Option<u64>,
+    /// The subscription plan
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    pub plan: SubPlan<'a>,
+}
+
+/// A gifted subscription being continued
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct GiftPaidUpgrade<'a> {
+    /// The login of the original gifter, if they weren't anonymous
+    pub gifter: Option<&'a str>,
+    /// The (unescaped) name of the ongoing subscription promo, if any
+    pub promo_name: Option<MaybeOwned<'a>>,
+    /// How many gifts the gifter has given during the promo, if any
+    pub promo_gift_total: Option<u64>,
+}
+
+/// A raid from another channel
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Raid<'a> {
+    /// The login of the raiding channel
+    pub source: &'a str,
+    /// The (unescaped) display name of the raiding channel
+    pub source_display_name: MaybeOwned<'a>,
+    /// How many viewers are coming along with the raid
+    pub viewer_count: u64,
+}
+
+/// A ritual
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct Ritual<'a> {
+    /// The name of the ritual, e.g. `new_chatter`
+    pub name: &'a str,
+}
+
+/// A new tier of the bits badge
+#[non_exhaustive]
+#[derive(Copy, Clone, Debug, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct BitsBadgeTier {
+    /// The tier that was earned, e.g. 100, 1000, 10000
+    pub threshold: u64,
+}
+
+impl<'a> UserNoticeEvent<'a> {
+    pub(super) fn from_tags(tags: Tags<'a>) -> Result<Self, MessageError> {
+        let params = Params(tags);
+
+        let this = match params.required("msg-id")? {
+            "sub" => Self::Sub(params.subscription()?),
+            "resub" => Self::Resub(params.subscription()?),
+            "subgift" => Self::SubGift(params.sub_gift(params.0.get("login"))?),
+            "anonsubgift" => Self::AnonSubGift(params.sub_gift(None)?),
+            "submysterygift" => Self::SubMysteryGift(SubMysteryGift {
+                gifter: params.0.get("login"),
+                count: params.parsed("msg-param-mass-gift-count")?,
+                total: params
+                    .optional("msg-param-sender-count")?
+                    .filter(|&n| n > 0),
+                plan: params.plan()?,
+            }),
+            "giftpaidupgrade" => Self::GiftPaidUpgrade(GiftPaidUpgrade {
+                gifter: Some(params.required("msg-param-sender-login")?),
+                promo_name: params.0.get_unescaped("msg-param-promo-name"),
+                promo_gift_total: params.optional("msg-param-promo-gift-total")?,
+            }),
+            "anongiftpaidupgrade" => Self::AnonGiftPaidUpgrade(GiftPaidUpgrade {
+                gifter: None,
+                promo_name: params.0.get_unescaped("msg-param-promo-name"),
+                promo_gift_total: params.optional("msg-param-promo-gift-total")?,
+            }),
+            "rewardgift" => Self::RewardGift,
+            "raid" => Self::Raid(Raid {
+                source: params.required("msg-param-login")?,
+                source_display_name: params.unescaped("msg-param-displayName")?,
+                viewer_count: params.parsed("msg-param-viewerCount")?,
+            }),
+            "unraid" => Self::Unraid,
+            "ritual" => Self::Ritual(Ritual {
+                name: params.required("msg-param-ritual-name")?,
+            }),
+            "bitsbadgetier" => Self::BitsBadgeTier(BitsBadgeTier {
+                threshold: params.parsed("msg-param-threshold")?,
+            }),
+            kind => Self::Unknown(kind),
+        };
+
+        Ok(this)
+    }
+}
+
+struct Params<'a>(Tags<'a>);
+
+impl<'a> Params<'a> {
+    fn required(&self, name: &str) -> Result<&'a str, MessageError> {
+        self.0.get(name).ok_or_else(|| MessageError::ExpectedTag {
+            name: name.to_string(),
+        })
+    }
+
+    fn unescaped(&self, name: &str) -> Result<MaybeOwned<'a>, MessageError> {
+        self.0
+            .get_unescaped(name)
+            .ok_or_else(|| MessageError::ExpectedTag {
+                name: name.to_string(),
+            })
+    }
+
+    fn parsed<T>(&self, name: &str) -> Result<T, MessageError>
+    where
+        T: FromStr,
+        T::Err: std::error::Error + Send + Sync + 'static,
+    {
+        self.optional(name)?
+            .ok_or_else(|| MessageError::ExpectedTag {
+                name: name.to_string(),
+            })
+    }
+
+    fn optional<T>(&self, name: &str) -> Result<Option<T>, MessageError>
+    where
+        T: FromStr,
+        T::Err: std::error::Error + Send + Sync + 'static,
+    {
+        self.0
+            .get(name)
+            .map(T::from_str)
+            .transpose()
+            .map_err(|err| MessageError::CannotParseTag {
+                name: name.to_string(),
+                error: Box::new(err),
+            })
+    }
+
+    fn plan(&self) -> Result<SubPlan<'a>, MessageError> {
+        self.required("msg-param-sub-plan").map(SubPlan::parse)
+    }
+
+    fn subscription(&self) -> Result<Subscription<'a>, MessageError> {
+        let streak = if self.0.get_as_bool("msg-param-should-share-streak") {
+            self.optional("msg-param-streak-months")?
+        } else {
+            None
+        };
+
+        Ok(Subscription {
+            months: self.parsed("msg-param-cumulative-months")?,
+            streak,
+            plan: self.plan()?,
+        })
+    }
+
+    fn sub_gift(&self, gifter: Option<&'a str>) -> Result<SubGift<'a>, MessageError> {
+        Ok(SubGift {
+            recipient_id: self.parsed("msg-param-recipient-id")?,
+            recipient_login: self.required("msg-param-recipient-user-name")?,
+            recipient_display_name: self.unescaped("msg-param-recipient-display-name")?,
+            gifter,
+            months: self.parsed("msg-param-months")?,
+            plan: self.plan()?,
+        })
+    }
+}
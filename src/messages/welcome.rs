@@ -0,0 +1,137 @@
+use crate::{irc::*, MaybeOwned, MaybeOwnedIndex, Validator};
+
+/// Which part of the welcome a [Welcome] message is
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub enum WelcomeKind {
+    /// The host you're connected to -- `002`
+    YourHost,
+    /// When the server was created -- `003`
+    Created,
+    /// Information about the server -- `004`
+    MyInfo,
+}
+
+/// The informational messages sent after an [IrcReady](super::IrcReady) -- `002`, `003` and `004`
+#[derive(Clone, PartialEq)]
+pub struct Welcome<'a> {
+    raw: MaybeOwned<'a>,
+    command: MaybeOwnedIndex,
+    username: MaybeOwnedIndex,
+    data: Option<MaybeOwnedIndex>,
+}
+
+impl<'a> Welcome<'a> {
+    raw!();
+    str_field!(
+        /// The name the server will refer to you as
+        username
+    );
+    opt_str_field!(
+        /// The informational text
+        data
+    );
+
+    /// Which part of the welcome this is
+    pub fn kind(&self) -> WelcomeKind {
+        match &self.raw[self.command] {
+            IrcMessage::YOUR_HOST => WelcomeKind::YourHost,
+            IrcMessage::CREATED => WelcomeKind::Created,
+            _ => WelcomeKind::MyInfo,
+        }
+    }
+}
+
+impl<'a> FromIrcMessage<'a> for Welcome<'a> {
+    type Error = MessageError;
+
+    fn from_irc(msg: IrcMessage<'a>) -> Result<Self, Self::Error> {
+        match msg.get_command() {
+            IrcMessage::YOUR_HOST | IrcMessage::CREATED | IrcMessage::MY_INFO => {}
+            got => {
+                return Err(MessageError::InvalidCommand {
+                    expected: format!(
+                        "{}, {} or {}",
+                        IrcMessage::YOUR_HOST,
+                        IrcMessage::CREATED,
+                        IrcMessage::MY_INFO
+                    ),
+                    got: got.to_string(),
+                })
+            }
+        }
+
+        let this = Self {
+            command: msg.command,
+            username: msg.expect_arg_index(0)?,
+            data: msg.data,
+            raw: msg.raw,
+        };
+
+        Ok(this)
+    }
+
+    into_inner_raw!();
+}
+
+into_owned!(Welcome {
+    raw,
+    command,
+    username,
+    data
+});
+impl_custom_debug!(Welcome {
+    raw,
+    kind,
+    username,
+    data
+});
+serde_struct!(Welcome {
+    raw,
+    kind,
+    username,
+    data
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn welcome_serde() {
+        let input = ":tmi.trovo.tv 002 shaken_bot :Your host is tmi.trovo.tv\r\n";
+        crate::serde::round_trip_json::<Welcome>(input);
+        crate::serde::round_trip_rmp::<Welcome>(input);
+    }
+
+    #[test]
+    fn welcome() {
+        let input = ":tmi.trovo.tv 002 shaken_bot :Your host is tmi.trovo.tv\r\n\
+                     :tmi.trovo.tv 003 shaken_bot :This server is rather new\r\n\
+                     :tmi.trovo.tv 004 shaken_bot :-\r\n";
+
+        let expected = &[
+            (WelcomeKind::YourHost, "Your host is tmi.trovo.tv"),
+            (WelcomeKind::Created, "This server is rather new"),
+            (WelcomeKind::MyInfo, "-"),
+        ];
+
+        for (msg, (kind, data)) in parse(input).map(|s| s.unwrap()).zip(expected) {
+            let msg = Welcome::from_irc(msg).unwrap();
+            assert_eq!(msg.kind(), *kind);
+            assert_eq!(msg.username(), "shaken_bot");
+            assert_eq!(msg.data().unwrap(), *data);
+        }
+    }
+
+    #[test]
+    fn welcome_wrong_command() {
+        let input = ":tmi.trovo.tv 001 shaken_bot :Welcome, GLHF!\r\n";
+        let msg = parse(input).next().unwrap().unwrap();
+        assert!(matches!(
+            Welcome::from_irc(msg).unwrap_err(),
+            MessageError::InvalidCommand { .. }
+        ));
+    }
+}
@@ -4,9 +4,9 @@ use crate::{
     commands,
     connector::Connector,
     encoder::AsyncEncoder,
-    messages::{Capability, Commands, MessageId},
+    messages::{Capability, Commands, MessageId, Received, Registry},
     rate_limit::{RateClass, RateLimit},
-    trovo::UserConfig,
+    trovo::{secret::mask_pass, UserConfig},
     util::{Notify, NotifyHandle},
     writer::{AsyncWriter, MpscWriter},
     AsyncDecoder, DecodeError, Encodable, FromIrcMessage, IrcMessage,
@@ -23,6 +23,7 @@ use std::{
     collections::{HashSet, VecDeque},
     pin::Pin,
     task::{Context, Poll},
+    time::SystemTime,
 };
 
 /// An asynchronous runner
@@ -47,7 +48,17 @@ pub struct AsyncRunner {
     writer: AsyncWriter<MpscWriter>,
     global_rate_limit: RateLimit,
 
-    missed_messages: VecDeque<Commands<'static>>,
+    missed_messages: VecDeque<Received<Commands<'static>>>,
+    // when the last message returned was received
+    last_received: Option<SystemTime>,
+    // reused between reads, along with whether the message should be returned
+    batch: Vec<(Commands<'static>, bool)>,
+    // an error from checking the batch, returned after the messages before it
+    batch_error: Option<Error>,
+
+    report_bad_lines: bool,
+
+    registry: Registry,
 }
 
 impl std::fmt::Debug for AsyncRunner {
@@ -72,6 +83,11 @@ impl AsyncRunner {
         log::debug!("registering");
         let mut buf = vec![];
         commands::register(user_config).encode(&mut buf)?;
+        log::trace!(
+            target: "trovochat::encoder",
+            "> {}",
+            mask_pass(&String::from_utf8_lossy(&buf)).escape_debug()
+        );
         stream.write_all(&buf).await?;
         log::debug!("registered");
 
@@ -125,9 +141,40 @@ impl AsyncRunner {
             global_rate_limit,
 
             missed_messages,
+            last_received: None,
+            batch: Vec::new(),
+            batch_error: None,
+
+            report_bad_lines: false,
+
+            registry: Registry::default(),
         })
     }
 
+    /// Replace any invalid UTF-8 read from the connection with `U+FFFD REPLACEMENT CHARACTER`.
+    ///
+    /// This is disabled by default, so invalid UTF-8 produces an error.
+    pub fn set_lossy(&mut self, lossy: bool) {
+        self.decoder.set_lossy(lossy);
+    }
+
+    /// Report lines that could not be decoded as a [Status::BadLine], rather than returning an error.
+    ///
+    /// This is disabled by default.
+    ///
+    /// I/O errors are still returned as errors.
+    pub fn set_report_bad_lines(&mut self, report: bool) {
+        self.report_bad_lines = report;
+    }
+
+    /// Use this [Registry] to parse commands that would otherwise be a [Commands::Raw]
+    ///
+    /// Messages with a registered command are yielded as a [Commands::Custom].
+    /// If the registered type fails to parse the message, it'll be left as a [Commands::Raw].
+    pub fn set_registry(&mut self, registry: Registry) {
+        self.registry = registry;
+    }
+
     /// Check whether you're on this channel
     pub fn is_on_channel(&self, channel: &str) -> bool {
         self.channels.is_on(channel)
@@ -168,32 +215,24 @@ impl AsyncRunner {
 
         let mut queue = VecDeque::new();
 
-        let status = self
-            .wait_for(&mut queue, |msg, this| match msg {
-                // check to see if it was us that joined the channel
-                Commands::Join(msg) => {
-                    Ok(msg.channel() == channel && msg.name() == this.identity.username())
-                }
-
-                // check to see if we were banned
-                Commands::Notice(msg) if matches!(msg.msg_id(), Some(MessageId::MsgBanned)) => {
-                    Err(Error::BannedFromChannel {
-                        channel: msg.channel().to_string(),
-                    })
-                }
-
-                _ => Ok(false),
-            })
-            .await?;
+        self.wait_for(&mut queue, |msg, this| match msg {
+            // check to see if it was us that joined the channel
+            Commands::Join(msg) => {
+                Ok(msg.channel() == channel && msg.name() == this.identity.username())
+            }
 
-        if let Some(status) = status {
-            match status {
-                Status::Quit | Status::Eof => return Err(Error::UnexpectedEof),
-                _ => unimplemented!(),
+            // check to see if we were banned
+            Commands::Notice(msg) if matches!(msg.msg_id(), Some(MessageId::MsgBanned)) => {
+                Err(Error::BannedFromChannel {
+                    channel: msg.channel().to_string(),
+                })
             }
-        }
 
-        self.missed_messages.extend(queue);
+            _ => Ok(false),
+        })
+        .await?;
+
+        self.requeue(queue);
 
         log::debug!("joined '{}'", channel);
 
@@ -216,35 +255,59 @@ impl AsyncRunner {
 
         let mut queue = VecDeque::new();
 
-        let status = self
-            .wait_for(&mut queue, |msg, this| match msg {
-                // check to see if it was us that left the channel
-                Commands::Part(msg) => {
-                    Ok(msg.channel() == channel && msg.name() == this.identity.username())
-                }
-                _ => Ok(false),
-            })
-            .await?;
-
-        if let Some(status) = status {
-            match status {
-                Status::Quit | Status::Eof => return Err(Error::UnexpectedEof),
-                _ => unimplemented!(),
+        self.wait_for(&mut queue, |msg, this| match msg {
+            // check to see if it was us that left the channel
+            Commands::Part(msg) => {
+                Ok(msg.channel() == channel && msg.name() == this.identity.username())
             }
-        }
+            _ => Ok(false),
+        })
+        .await?;
+
         log::debug!("left '{}'", channel);
 
-        self.missed_messages.extend(queue);
+        self.requeue(queue);
 
         Ok(())
     }
 
     /// Get the next message. You'll usually want to call this in a loop
     pub async fn next_message(&mut self) -> Result<Status<'static>, Error> {
+        self.next_message_filtered(|_| true).await
+    }
+
+    /// Get the next message that `filter` accepts.
+    ///
+    /// Each message is handed to `filter` while it is still borrowed from the read buffer, and it is only copied into an owned message if `filter` returns true.
+    /// Messages that it rejects are skipped, but the runner still handles the ones it needs (such as a `PING` or a `RECONNECT`).
+    ///
+    /// ```no_run
+    /// # use trovochat::{AsyncRunner, messages::Commands, runner::Status};
+    /// # async fn demo(runner: &mut AsyncRunner) -> Result<(), trovochat::runner::Error> {
+    /// // only allocate for messages that mention us
+    /// while let Status::Message(msg) = runner
+    ///     .next_message_filtered(|msg| match msg {
+    ///         Commands::Privmsg(pm) => pm.data().contains("museun"),
+    ///         _ => false,
+    ///     })
+    ///     .await?
+    /// {
+    ///     println!("{}", msg.raw());
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn next_message_filtered<F>(
+        &mut self,
+        mut filter: F,
+    ) -> Result<Status<'static>, Error>
+    where
+        F: FnMut(&Commands<'_>) -> bool + Send + Sync,
+    {
         use crate::util::{Either::*, FutExt as _};
 
         loop {
-            match self.step().await? {
+            match self.step_filtered(&mut filter).await? {
                 StepResult::Nothing => continue,
                 StepResult::Status(Status::Quit) => {
                     if let Left(_notified) = self.notify.wait().now_or_never().await {
@@ -270,18 +333,72 @@ impl AsyncRunner {
         }
     }
 
+    /// Get the next message along with the local time it was received
+    ///
+    /// This returns None once the loop has quit or reached the end. Any bad lines are skipped.
+    pub async fn next_received(&mut self) -> Result<Option<Received<Commands<'static>>>, Error> {
+        loop {
+            match self.next_message().await? {
+                Status::Message(msg) => {
+                    let received = self.last_received.unwrap_or_else(SystemTime::now);
+                    break Ok(Some(Received::with_time(msg, received)));
+                }
+                Status::BadLine { .. } => continue,
+                Status::Quit | Status::Eof => break Ok(None),
+            }
+        }
+    }
+
+    /// The local time that the last message returned by [AsyncRunner::next_message()] was received
+    pub fn last_received(&self) -> Option<SystemTime> {
+        self.last_received
+    }
+
     /// Single step the loop. This is useful for testing.
     pub async fn step(&mut self) -> Result<StepResult<'static>, Error> {
-        use crate::util::*;
-        use crate::IntoOwned as _;
+        self.step_filtered(&mut |_| true).await
+    }
+
+    async fn step_filtered<F>(&mut self, filter: &mut F) -> Result<StepResult<'static>, Error>
+    where
+        F: FnMut(&Commands<'_>) -> bool + Send + Sync,
+    {
+        use crate::{util::*, IntoOwned as _};
+
+        while let Some(msg) = self.missed_messages.pop_front() {
+            if filter(msg.message()) {
+                return Ok(StepResult::Status(self.received(msg)));
+            }
+        }
 
-        if let Some(msg) = self.missed_messages.pop_front() {
-            return Ok(StepResult::Status(Status::Message(msg)));
+        if let Some(err) = self.batch_error.take() {
+            return Err(err);
         }
 
-        let select = self
-            .decoder
-            .read_message()
+        // whatever came after the error in the last batch
+        if !self.batch.is_empty() {
+            self.check_batch(SystemTime::now()).await;
+            if let Some(msg) = self.missed_messages.pop_front() {
+                return Ok(StepResult::Status(self.received(msg)));
+            }
+            if let Some(err) = self.batch_error.take() {
+                return Err(err);
+            }
+        }
+
+        let registry = &self.registry;
+        let identity = &self.identity;
+        let read = self.decoder.read_batch_with(&mut self.batch, |mut msg| {
+            Self::resolve_custom(registry, &mut msg);
+            let wanted = filter(&msg);
+            if wanted || Self::is_handled(identity, &msg) {
+                Some((msg.into_owned(), wanted))
+            } else {
+                None
+            }
+        });
+
+        let select = read
             .either(self.activity_rx.recv())
             .either(self.writer_rx.recv())
             .either(self.notify.wait())
@@ -289,28 +406,41 @@ impl AsyncRunner {
             .await;
 
         match select {
-            Left(Left(Left(Left(msg)))) => {
-                let msg = match msg {
+            Left(Left(Left(Left(read)))) => {
+                match read {
                     Err(DecodeError::Eof) => {
                         log::info!("got an EOF, exiting main loop");
                         return Ok(StepResult::Status(Status::Eof));
                     }
+                    Err(err @ DecodeError::Io(..)) => {
+                        log::warn!("read an error: {}", err);
+                        return Err(err.into());
+                    }
+                    Err(error) if self.report_bad_lines => {
+                        log::warn!("read a bad line: {}", error);
+                        self.timeout_state = TimeoutState::activity();
+                        let raw = self.decoder.last_line().into();
+                        return Ok(StepResult::Status(Status::BadLine { raw, error }));
+                    }
                     Err(err) => {
                         log::warn!("read an error: {}", err);
                         return Err(err.into());
                     }
-                    Ok(msg) => msg,
-                };
+                    Ok(n) => log::trace!("read a batch of {} message(s)", n),
+                }
 
                 self.timeout_state = TimeoutState::activity();
 
-                let all = Commands::from_irc(msg) //
-                    .expect("msg identity conversion should be upheld")
-                    .into_owned();
+                // the whole batch was read at the same time
+                let now = SystemTime::now();
 
-                self.check_messages(&all).await?;
-
-                return Ok(StepResult::Status(Status::Message(all)));
+                self.check_batch(now).await;
+                if let Some(msg) = self.missed_messages.pop_front() {
+                    return Ok(StepResult::Status(self.received(msg)));
+                }
+                if let Some(err) = self.batch_error.take() {
+                    return Err(err);
+                }
             }
 
             Left(Left(Left(Right(Some(_activity))))) => {
@@ -318,14 +448,12 @@ impl AsyncRunner {
             }
 
             Left(Left(Right(Some(write_data)))) => {
-                // TODO provide a 'bytes' flavored parser
-                let msg = std::str::from_utf8(&*write_data).map_err(Error::InvalidUtf8)?;
-                let res = crate::irc::parse_one(msg) //
+                let (_, msg) = crate::irc::parse_one_bytes(&*write_data) //
                     .expect("encoder should produce valid IRC messages");
-                let msg = res.1;
 
-                if let crate::irc::IrcMessage::PRIVMSG = msg.get_command() {
-                    if let Some(ch) = msg.nth_arg(0) {
+                // only the command and channel have to be valid utf-8 here
+                if let Ok(IrcMessage::PRIVMSG) = msg.get_command() {
+                    if let Some(ch) = msg.nth_arg(0).transpose().map_err(Error::InvalidUtf8)? {
                         if !self.channels.is_on(ch) {
                             self.channels.add(ch)
                         }
@@ -378,10 +506,61 @@ impl AsyncRunner {
         Ok(StepResult::Nothing)
     }
 
+    fn resolve_custom(registry: &Registry, msg: &mut Commands<'_>) {
+        let raw = match msg {
+            Commands::Raw(raw) => raw,
+            _ => return,
+        };
+
+        match registry.parse_custom(raw) {
+            Some(Ok(custom)) => *msg = Commands::Custom(custom),
+            Some(Err(err)) => log::warn!("cannot parse registered command: {}", err),
+            None => {}
+        }
+    }
+
+    // checks the batch in order, queueing the wanted messages. the queue is empty here, so they stay in order.
+    // if a check fails, the error is returned after the messages before it, and the rest of the batch is kept
+    async fn check_batch(&mut self, now: SystemTime) {
+        let mut batch = std::mem::take(&mut self.batch);
+        let mut iter = batch.drain(..);
+        while let Some((msg, wanted)) = iter.next() {
+            if let Err(err) = self.check_messages(&msg).await {
+                self.batch_error.replace(err);
+                self.batch = iter.collect();
+                return;
+            }
+            if wanted {
+                self.missed_messages
+                    .push_back(Received::with_time(msg, now));
+            }
+        }
+        drop(iter);
+        self.batch = batch;
+    }
+
+    fn received(&mut self, msg: Received<Commands<'static>>) -> Status<'static> {
+        self.last_received.replace(msg.received());
+        Status::Message(msg.into_inner())
+    }
+
+    // whether check_messages does anything with this message. keep these in sync
+    fn is_handled(identity: &Identity, msg: &Commands<'_>) -> bool {
+        use Commands::*;
+
+        match msg {
+            Join(msg) => msg.name() == identity.username(),
+            Part(msg) => msg.name() == identity.username(),
+            Ping(..) | Pong(..) | RoomState(..) | Notice(..) | Reconnect(..)
+            | UnknownCommand(..) => true,
+            _ => false,
+        }
+    }
+
     async fn check_messages(&mut self, all: &Commands<'static>) -> Result<(), Error> {
         use {Commands::*, TimeoutState::*};
 
-        log::trace!("< {}", all.raw().escape_debug());
+        log::trace!("< {}", mask_pass(all.raw()).escape_debug());
 
         match &all {
             Ping(msg) => {
@@ -433,6 +612,8 @@ impl AsyncRunner {
 
             Reconnect(_) => return Err(Error::ShouldReconnect),
 
+            UnknownCommand(msg) => log::warn!("{}", msg),
+
             _ => {}
         }
 
@@ -443,9 +624,9 @@ impl AsyncRunner {
 impl AsyncRunner {
     async fn wait_for<F>(
         &mut self,
-        missed: &mut VecDeque<Commands<'static>>,
+        missed: &mut VecDeque<Received<Commands<'static>>>,
         func: F,
-    ) -> Result<Option<Status<'static>>, Error>
+    ) -> Result<(), Error>
     where
         F: Fn(&Commands<'static>, &Self) -> Result<bool, Error> + Send + Sync,
     {
@@ -453,16 +634,27 @@ impl AsyncRunner {
             match self.step().await? {
                 StepResult::Status(Status::Message(msg)) => {
                     if func(&msg, self)? {
-                        break Ok(None);
+                        break Ok(());
                     }
-                    missed.push_back(msg);
+                    let received = self.last_received.unwrap_or_else(SystemTime::now);
+                    missed.push_back(Received::with_time(msg, received));
+                }
+                // the line was already logged, so just keep waiting
+                StepResult::Status(Status::BadLine { .. }) => continue,
+                StepResult::Status(Status::Quit) | StepResult::Status(Status::Eof) => {
+                    break Err(Error::UnexpectedEof)
                 }
-                StepResult::Status(d) => return Ok(Some(d)),
                 StepResult::Nothing => continue,
             }
         }
     }
 
+    // the messages seen while waiting came before anything left over from their batch
+    fn requeue(&mut self, mut queue: VecDeque<Received<Commands<'static>>>) {
+        queue.extend(self.missed_messages.drain(..));
+        self.missed_messages = queue;
+    }
+
     fn available_queued_messages(&self) -> usize {
         self.channels
             .map
@@ -519,7 +711,7 @@ impl AsyncRunner {
         decoder: &mut AsyncDecoder<R>,
         encoder: &mut AsyncEncoder<W>,
         user_config: &UserConfig,
-        missed_messages: &mut VecDeque<Commands<'static>>,
+        missed_messages: &mut VecDeque<Received<Commands<'static>>>,
     ) -> Result<Identity, Error>
     where
         R: AsyncRead + Send + Sync + Unpin,
@@ -529,11 +721,24 @@ impl AsyncRunner {
 
         let is_anonymous = user_config.is_anonymous();
 
-        let mut looking_for: HashSet<_> = user_config.capabilities.iter().collect();
+        use crate::trovo::Capability as TrovoCap;
+
+        let requested = || {
+            user_config
+                .capabilities
+                .iter()
+                .chain(&user_config.optional_capabilities)
+        };
+
+        let mut looking_for: HashSet<_> = user_config.requested_capabilities().collect();
         let mut caps = Capabilities::default();
         let mut our_name = None;
 
-        use crate::trovo::Capability as TrovoCap;
+        // sending a `CAP LS` starts a negotiation, and the server will hold our
+        // registration until we end it
+        let mut negotiating = user_config.list_capabilities;
+        let mut waiting_for_ls = user_config.list_capabilities;
+
         // Trovo says we'll be getting a GlobalUserState if we just send the
         // Tags capability
         //
@@ -546,9 +751,12 @@ impl AsyncRunner {
         //
         // We'll check for both Tags and Commands
         //
-        let will_be_getting_global_user_state_hopefully =
-            user_config.capabilities.contains(&TrovoCap::Tags) &&
-            user_config.capabilities.contains(&TrovoCap::Commands);
+        // If either of these are optional and get rejected, we won't be
+        // getting it
+        //
+        let mut will_be_getting_global_user_state_hopefully = requested()
+            .any(|cap| *cap == TrovoCap::Tags)
+            && requested().any(|cap| *cap == TrovoCap::Commands);
 
         let identity = loop {
             let msg: IrcMessage<'_> = decoder.read_message().await?;
@@ -559,7 +767,7 @@ impl AsyncRunner {
             let commands = Commands::from_irc(msg)?;
 
             // this is the simpliest way. and this'll only clone like 9 messages
-            missed_messages.push_back(commands.clone().into_owned());
+            missed_messages.push_back(Received::new(commands.clone().into_owned()));
 
             match commands {
                 Ready(msg) => {
@@ -585,34 +793,60 @@ impl AsyncRunner {
                     }
                 }
 
-                Cap(msg) => match msg.capability() {
-                    Capability::Acknowledged(name) => {
-                        use crate::trovo::Capability as Cap;
+                Cap(msg) => {
+                    if matches!(msg.capability(), Capability::Listed(..)) && !msg.is_continued() {
+                        waiting_for_ls = false;
+                    }
 
-                        let cap = match Cap::maybe_from_str(name) {
-                            Some(cap) => cap,
-                            // Trovo sent us an unknown capability
-                            None => {
-                                caps.unknown.insert(name.to_string());
-                                continue;
+                    for cap in msg.capabilities() {
+                        match cap {
+                            Capability::Acknowledged(name) => {
+                                caps.acknowledge(name);
+                                looking_for.remove(name);
                             }
-                        };
 
-                        *match cap {
-                            Cap::Tags => &mut caps.tags,
-                            Cap::Membership => &mut caps.membership,
-                            Cap::Commands => &mut caps.commands,
-                        } = true;
+                            Capability::NotAcknowledged(name) => {
+                                if user_config.is_required(name) {
+                                    return Err(Error::InvalidCap {
+                                        cap: name.to_string(),
+                                    });
+                                }
+
+                                log::warn!("optional capability '{}' was not acknowledged", name);
+                                if let Some(TrovoCap::Tags) | Some(TrovoCap::Commands) =
+                                    TrovoCap::maybe_from_str(name)
+                                {
+                                    will_be_getting_global_user_state_hopefully = false;
+                                }
+                                caps.rejected.insert(name.to_string());
+                                looking_for.remove(name);
+                            }
 
-                        looking_for.remove(&cap);
+                            // this can be in the form of 'name=value'
+                            Capability::Listed(name) => {
+                                let name = name.split('=').next().unwrap_or(name);
+                                caps.available.insert(name.to_string());
+                            }
+                        }
                     }
 
-                    Capability::NotAcknowledged(name) => {
-                        return Err(Error::InvalidCap {
-                            cap: name.to_string(),
-                        })
+                    if negotiating && !waiting_for_ls && looking_for.is_empty() {
+                        log::debug!("ending capability negotiation");
+                        encoder.encode(commands::raw("CAP END")).await?;
+                        negotiating = false;
                     }
-                },
+
+                    // we might've been waiting on the last cap after we got our name
+                    if our_name.is_some()
+                        && !will_be_getting_global_user_state_hopefully
+                        && looking_for.is_empty()
+                    {
+                        break Identity::Basic {
+                            name: our_name.take().unwrap(),
+                            caps,
+                        };
+                    }
+                }
 
                 // NOTE: This will only be sent when there's both Commands and atleast one other CAP requested
                 GlobalUserState(msg) => {
@@ -647,7 +881,10 @@ impl AsyncRunner {
 
                 _ => {
                     // we have our name, but we won't be getting GlobalUserState and we've got all of our Caps
-                    if our_name.is_some() && !will_be_getting_global_user_state_hopefully && looking_for.is_empty() {
+                    if our_name.is_some()
+                        && !will_be_getting_global_user_state_hopefully
+                        && looking_for.is_empty()
+                    {
                         break Identity::Basic {
                             name: our_name.take().unwrap(),
                             caps,
@@ -673,9 +910,141 @@ impl Stream for AsyncRunner {
             Ok(status) => match status {
                 Status::Message(msg) => Poll::Ready(Some(msg)),
                 Status::Quit | Status::Eof => Poll::Ready(None),
+                // bad lines aren't messages, so just try again
+                Status::BadLine { .. } => {
+                    ctx.waker().wake_by_ref();
+                    Poll::Pending
+                }
             },
             Err(..) => Poll::Ready(None),
         }
     }
 }
 }
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::*;
+    use crate::test::TestConnector;
+
+    const JOIN: &str = ":justinfan1234!justinfan1234@justinfan1234.tmi.trovo.tv JOIN #museun\r\n";
+
+    async fn connect(connector: &TestConnector) -> AsyncRunner {
+        connector
+            .conn
+            .write_data(":tmi.trovo.tv 376 justinfan1234 :>\r\n")
+            .await;
+
+        let user_config = UserConfig::builder().anonymous().build().unwrap();
+        let mut runner = AsyncRunner::connect(connector.clone(), &user_config)
+            .await
+            .unwrap();
+
+        // skip the messages seen while connecting
+        runner.missed_messages.clear();
+        runner
+    }
+
+    #[test]
+    fn cap_end_after_negotiation() {
+        let fut = async move {
+            let connector = TestConnector::default();
+            connector
+                .conn
+                .write_data(
+                    ":tmi.trovo.tv CAP * LS * :trovo.tv/membership\r\n\
+                     :tmi.trovo.tv CAP * LS :trovo.tv/tags\r\n\
+                     :tmi.trovo.tv CAP * ACK :trovo.tv/tags\r\n\
+                     :tmi.trovo.tv 376 justinfan1234 :>\r\n",
+                )
+                .await;
+
+            let user_config = UserConfig::builder()
+                .anonymous()
+                .capabilities(&[crate::trovo::Capability::Tags])
+                .list_capabilities()
+                .build()
+                .unwrap();
+            let _runner = AsyncRunner::connect(connector.clone(), &user_config)
+                .await
+                .unwrap();
+
+            let lines = connector.conn.read_all_lines().await.unwrap();
+            assert_eq!(lines.first().unwrap(), "CAP LS 302\r\n");
+            assert_eq!(lines.last().unwrap(), "CAP END\r\n");
+            assert_eq!(lines.iter().filter(|s| *s == "CAP END\r\n").count(), 1);
+        };
+        futures_lite::future::block_on(fut);
+    }
+
+    #[test]
+    fn join_skips_bad_lines() {
+        let fut = async move {
+            let connector = TestConnector::default();
+            let mut runner = connect(&connector).await;
+            runner.set_report_bad_lines(true);
+
+            let mut input = b"PRIVMSG #museun :\xFF\r\n".to_vec();
+            input.extend_from_slice(JOIN.as_bytes());
+            connector.conn.write_data(input).await;
+
+            runner.join("#museun").await.unwrap();
+            assert!(runner.is_on_channel("#museun"));
+        };
+        futures_lite::future::block_on(fut);
+    }
+
+    #[test]
+    fn join_keeps_order() {
+        let fut = async move {
+            let connector = TestConnector::default();
+            let mut runner = connect(&connector).await;
+
+            let input = format!(
+                ":museun!museun@museun.tmi.trovo.tv PRIVMSG #museun :a\r\n{}\
+                 :museun!museun@museun.tmi.trovo.tv PRIVMSG #museun :b\r\n",
+                JOIN
+            );
+            connector.conn.write_data(input).await;
+
+            runner.join("#museun").await.unwrap();
+
+            let mut data = vec![];
+            for _ in 0..2 {
+                match runner.next_message().await.unwrap() {
+                    Status::Message(Commands::Privmsg(msg)) => data.push(msg.data().to_string()),
+                    status => panic!("unexpected status: {:?}", status),
+                }
+            }
+            assert_eq!(data, vec!["a", "b"]);
+        };
+        futures_lite::future::block_on(fut);
+    }
+
+    #[test]
+    fn reconnect_keeps_earlier_messages() {
+        let fut = async move {
+            let connector = TestConnector::default();
+            let mut runner = connect(&connector).await;
+
+            let input = ":museun!museun@museun.tmi.trovo.tv PRIVMSG #museun :a\r\n\
+                         RECONNECT\r\n\
+                         :museun!museun@museun.tmi.trovo.tv PRIVMSG #museun :b\r\n";
+            connector.conn.write_data(input).await;
+
+            match runner.next_message().await.unwrap() {
+                Status::Message(Commands::Privmsg(msg)) => assert_eq!(msg.data(), "a"),
+                status => panic!("unexpected status: {:?}", status),
+            }
+            assert!(matches!(
+                runner.next_message().await,
+                Err(Error::ShouldReconnect)
+            ));
+            match runner.next_message().await.unwrap() {
+                Status::Message(Commands::Privmsg(msg)) => assert_eq!(msg.data(), "b"),
+                status => panic!("unexpected status: {:?}", status),
+            }
+        };
+        futures_lite::future::block_on(fut);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::trovo::Capability;
 use std::collections::HashSet;
 
 /// Capabiltiies Trovo acknowledged.
@@ -10,5 +11,88 @@ pub struct Capabilities {
     /// You have the [tags](https://dev.trovo.tv/docs/irc/tags) capability
     pub tags: bool,
     /// A set of unknown capabilities Trovo sent to use
+    ///
+    /// These are any acknowledged capabilities that aren't one of the Trovo ones (e.g. a [custom capability])
+    ///
+    /// [custom capability]: crate::trovo::UserConfigBuilder::custom_capabilities
     pub unknown: HashSet<String>,
+    /// A set of optional capabilities that Trovo rejected
+    pub rejected: HashSet<String>,
+    /// A set of capabilities Trovo said were available
+    ///
+    /// This is only filled if [UserConfig::list_capabilities] was enabled.
+    ///
+    /// [UserConfig::list_capabilities]: crate::trovo::UserConfig::list_capabilities
+    pub available: HashSet<String>,
+}
+
+impl Capabilities {
+    /// Determines whether this capability was acknowledged
+    pub fn is_enabled(&self, cap: Capability) -> bool {
+        match cap {
+            Capability::Membership => self.membership,
+            Capability::Tags => self.tags,
+            Capability::Commands => self.commands,
+        }
+    }
+
+    /// Determines whether the capability with this name was acknowledged
+    ///
+    /// This works for both the Trovo capabilities and any custom ones.
+    pub fn is_enabled_by_name(&self, name: &str) -> bool {
+        match Capability::maybe_from_str(name) {
+            Some(cap) => self.is_enabled(cap),
+            None => self.unknown.contains(name),
+        }
+    }
+
+    /// Determines whether Trovo listed the capability with this name as available
+    pub fn is_available(&self, name: &str) -> bool {
+        self.available.contains(name)
+    }
+
+    /// An iterator over all of the acknowledged Trovo capabilities
+    ///
+    /// Any custom capabilities are in [Capabilities::unknown]
+    pub fn enabled(&self) -> impl Iterator<Item = Capability> + '_ {
+        vec![
+            Capability::Membership,
+            Capability::Tags,
+            Capability::Commands,
+        ]
+        .into_iter()
+        .filter(move |&cap| self.is_enabled(cap))
+    }
+
+    pub(crate) fn acknowledge(&mut self, name: &str) {
+        *match Capability::maybe_from_str(name) {
+            Some(Capability::Membership) => &mut self.membership,
+            Some(Capability::Tags) => &mut self.tags,
+            Some(Capability::Commands) => &mut self.commands,
+            None => {
+                self.unknown.insert(name.to_string());
+                return;
+            }
+        } = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_enabled() {
+        let mut caps = Capabilities::default();
+        caps.acknowledge("trovo.tv/tags");
+        caps.acknowledge("example.com/foo");
+
+        assert!(caps.is_enabled(Capability::Tags));
+        assert!(!caps.is_enabled(Capability::Commands));
+        assert!(caps.is_enabled_by_name("trovo.tv/tags"));
+        assert!(caps.is_enabled_by_name("example.com/foo"));
+        assert!(!caps.is_enabled_by_name("example.com/bar"));
+
+        assert_eq!(caps.enabled().collect::<Vec<_>>(), vec![Capability::Tags]);
+    }
 }
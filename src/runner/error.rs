@@ -10,6 +10,8 @@ pub enum Error {
     /// We could not parse a message -- this should never happen
     ParsingFailure(MessageError),
     /// You requested a capability and Trovo rejected it
+    ///
+    /// This is only returned for required capabilities. See [UserConfig::optional_capabilities](crate::trovo::UserConfig::optional_capabilities)
     InvalidCap {
         /// The capability name
         cap: String,
@@ -35,6 +37,11 @@ pub enum Error {
     ShouldReconnect,
     /// An unexpected EOF was found -- this means the connectionc losed abnormally.
     UnexpectedEof,
+    /// A line longer than the maximum allowed length was read
+    LineTooLong {
+        /// The maximum length, in bytes
+        max: usize,
+    },
 }
 
 impl std::fmt::Display for Error {
@@ -52,6 +59,7 @@ impl std::fmt::Display for Error {
             Self::TimedOut => write!(f, "your connection timed out"),
             Self::ShouldReconnect => write!(f, "you should reconnect. Trovo restarted the server"),
             Self::UnexpectedEof => write!(f, "reached an unexpected EOF"),
+            Self::LineTooLong { max } => write!(f, "read a line longer than {} bytes", max),
         }
     }
 }
@@ -74,6 +82,7 @@ impl From<DecodeError> for Error {
             DecodeError::InvalidUtf8(err) => Self::InvalidUtf8(err),
             DecodeError::ParseError(err) => Self::ParsingFailure(err),
             DecodeError::Eof => Self::UnexpectedEof,
+            DecodeError::LineTooLong { max } => Self::LineTooLong { max },
         }
     }
 }
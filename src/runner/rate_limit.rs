@@ -1,4 +1,7 @@
-use crate::rate_limit::{RateClass, RateLimit};
+use crate::{
+    rate_limit::{RateClass, RateLimit},
+    trovo::secret::mask_pass,
+};
 use futures_lite::{AsyncWrite, AsyncWriteExt};
 use std::{collections::VecDeque, time::Duration};
 
@@ -24,7 +27,7 @@ impl RateLimitedEncoder {
                     log::trace!(
                         target: "trovochat::encoder",
                         "> {}",
-                        std::str::from_utf8(&*data).unwrap().escape_debug()
+                        mask_pass(&String::from_utf8_lossy(&*data)).escape_debug()
                     );
                     sink.write_all(&*data).await?;
                 }
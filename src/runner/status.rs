@@ -1,4 +1,4 @@
-use crate::messages::Commands;
+use crate::{messages::Commands, DecodeError};
 
 /// Result of a single step of the loop
 #[derive(Debug)]
@@ -18,4 +18,13 @@ pub enum Status<'a> {
     Quit,
     /// Loop run to completion
     Eof,
+    /// A line could not be decoded
+    ///
+    /// This is only produced when reporting bad lines is enabled on the runner, otherwise it is returned as an error.
+    BadLine {
+        /// The raw bytes of the line
+        raw: Box<[u8]>,
+        /// Why the line could not be decoded
+        error: DecodeError,
+    },
 }
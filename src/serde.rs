@@ -12,12 +12,19 @@ impl<'de, 'a> Deserialize<'de> for MaybeOwned<'a> {
     where
         D: Deserializer<'de>,
     {
-        <Box<str>>::deserialize(deserializer).map(MaybeOwned::Owned)
+        <Box<str>>::deserialize(deserializer).map(|s| MaybeOwned::Shared(s.into()))
     }
 }
 
+/// A visitor that parses a message from its `raw` field
 pub struct RawVisitor<'a, T>(PhantomData<&'a T>);
 
+impl<'a, T> std::fmt::Debug for RawVisitor<'a, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RawVisitor").finish()
+    }
+}
+
 impl<'a, T> Default for RawVisitor<'a, T> {
     fn default() -> Self {
         Self(PhantomData)
@@ -1,3 +1,5 @@
+use crate::messages::SubPlan;
+
 /// The kind of the [badges] that are associated with messages.
 ///
 /// Any unknown (e.g. custom badges/sub events, etc) are placed into the [Unknown] variant.
@@ -30,6 +32,28 @@ pub enum BadgeKind<'a> {
     VIP,
     /// Partner badge
     Partner,
+    /// Founder badge, for the first subscribers of a channel
+    Founder,
+    /// Predictions badge, showing which outcome the user picked
+    Predictions,
+    /// Hype train badge
+    HypeTrain,
+    /// Sub gifter badge
+    SubGifter,
+    /// Sub gift leaderboard badge
+    SubGiftLeader,
+    /// Bits leaderboard badge
+    BitsLeader,
+    /// Bits charity badge
+    BitsCharity,
+    /// Artist badge
+    Artist,
+    /// Ambassador badge
+    Ambassador,
+    /// Watching without audio badge
+    NoAudio,
+    /// Watching without video badge
+    NoVideo,
     /// Unknown badge. Likely a custom badge
     Unknown(&'a str),
 }
@@ -67,14 +91,122 @@ impl<'a> Badge<'a> {
             "premium" => Premium,
             "vip" => VIP,
             "partner" => Partner,
+            "founder" => Founder,
+            "predictions" => Predictions,
+            "hype-train" => HypeTrain,
+            "sub-gifter" => SubGifter,
+            "sub-gift-leader" => SubGiftLeader,
+            "bits-leader" => BitsLeader,
+            "bits-charity" => BitsCharity,
+            "artist-badge" => Artist,
+            "ambassador" => Ambassador,
+            "no_audio" => NoAudio,
+            "no_video" => NoVideo,
             badge => Unknown(badge),
         };
 
         iter.next().map(|data| Badge { kind, data })
     }
+
+    /// The data of this badge as a number
+    ///
+    /// For [BadgeInfo] this is the number of months for a `subscriber` badge, or the founder number for a `founder` badge.
+    pub fn number(&self) -> Option<u64> {
+        self.data.parse().ok()
+    }
+
+    /// The subscription tier of a `subscriber` badge
+    ///
+    /// The badge versions for tier 2 and 3 subscribers start at `2000` and `3000`, every other version is tier 1.
+    pub fn sub_tier(&self) -> Option<SubPlan<'static>> {
+        if self.kind != BadgeKind::Subscriber {
+            return None;
+        }
+        match self.number()? / 1000 {
+            0 | 1 => SubPlan::Tier1,
+            2 => SubPlan::Tier2,
+            3 => SubPlan::Tier3,
+            _ => return None,
+        }
+        .into()
+    }
+
+    /// The outcome chosen for a `predictions` badge, e.g. `blue-1` or `pink-2`
+    pub fn prediction(&self) -> Option<PredictionChoice<'a>> {
+        if self.kind != BadgeKind::Predictions {
+            return None;
+        }
+        let pos = self.data.rfind('-')?;
+        PredictionChoice {
+            color: &self.data[..pos],
+            outcome: self.data[pos + 1..].parse().ok()?,
+        }
+        .into()
+    }
+}
+
+/// The outcome a user picked for a prediction, from their `predictions` badge
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+pub struct PredictionChoice<'a> {
+    /// The color of the outcome, e.g. `blue` or `pink`
+    pub color: &'a str,
+    /// The number of the outcome, starting at `1`
+    pub outcome: u8,
 }
 
 /// Metadata to the chat badges
 pub type BadgeInfo<'a> = Badge<'a>;
 
-// TODO tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let badges = crate::trovo::parse_badges("founder/0,predictions/pink-2,hype-train/1,foo/1");
+        let kinds = badges.iter().map(|b| b.kind.clone()).collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                BadgeKind::Founder,
+                BadgeKind::Predictions,
+                BadgeKind::HypeTrain,
+                BadgeKind::Unknown("foo")
+            ]
+        );
+    }
+
+    #[test]
+    fn sub_tier() {
+        let tier = |s| Badge::parse(s).unwrap().sub_tier();
+        assert_eq!(tier("subscriber/0"), Some(SubPlan::Tier1));
+        assert_eq!(tier("subscriber/12"), Some(SubPlan::Tier1));
+        assert_eq!(tier("subscriber/2003"), Some(SubPlan::Tier2));
+        assert_eq!(tier("subscriber/3012"), Some(SubPlan::Tier3));
+        assert_eq!(tier("subscriber/9000"), None);
+        assert_eq!(tier("bits/2000"), None);
+    }
+
+    #[test]
+    fn prediction() {
+        let prediction = |s| Badge::parse(s).unwrap().prediction();
+        assert_eq!(
+            prediction("predictions/blue-1"),
+            Some(PredictionChoice {
+                color: "blue",
+                outcome: 1
+            })
+        );
+        assert_eq!(
+            prediction("predictions/pink-2"),
+            Some(PredictionChoice {
+                color: "pink",
+                outcome: 2
+            })
+        );
+        assert_eq!(prediction("predictions/Blue\\sSide"), None);
+        assert_eq!(prediction("subscriber/1"), None);
+    }
+}
@@ -1,6 +1,10 @@
 /// Capability used to enable extra functionality with the protocol
 ///
 /// Without any of these specified, you will just able to read/write basic messages
+///
+/// Any other capability can be requested by name with [UserConfigBuilder::custom_capabilities].
+///
+/// [UserConfigBuilder::custom_capabilities]: crate::trovo::UserConfigBuilder::custom_capabilities
 #[non_exhaustive]
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -29,10 +33,18 @@ impl Capability {
         }
     }
 
+    /// Get the name of this capability, as it is known to the server
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Membership => "trovo.tv/membership",
+            Self::Tags => "trovo.tv/tags",
+            Self::Commands => "trovo.tv/commands",
+        }
+    }
+
     /// Attempts to 'parse' this capability from a string
     ///
     /// This will take the form of `trovo.tv/$tag` and produce a [Capability]
-    #[allow(dead_code)]
     pub(crate) fn maybe_from_str(input: &str) -> Option<Self> {
         match input {
             "trovo.tv/membership" => Some(Self::Membership),
@@ -42,3 +54,9 @@ impl Capability {
         }
     }
 }
+
+impl std::fmt::Display for Capability {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
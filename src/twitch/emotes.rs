@@ -3,12 +3,14 @@ use std::ops::Range;
 /**
 Emotes are little pictograms used in-line in Trovo messages
 
-They are presented (to the irc connection) in a `id:range1,range2/id2:range1,..` form which marks the byte position that the emote is located.
+They are presented (to the irc connection) in a `id:range1,range2/id2:range1,..` form which marks the (inclusive) code point positions where the emote is located.
+
+Use [Privmsg::fragments()](crate::messages::Privmsg::fragments) to get the emotes along with their text.
 
 # example:
-`"testing Kappa"` would be `25:8-13`
+`"testing Kappa"` would be `25:8-12`
 
-`"Kappa testing Kappa"` would be `25:0-5,14-19`
+`"Kappa testing Kappa"` would be `25:0-4,14-18`
 */
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
@@ -3,6 +3,10 @@
 mod capability;
 pub use capability::Capability;
 
+pub mod secret;
+#[doc(inline)]
+pub use secret::Secret;
+
 mod userconfig;
 pub use userconfig::{UserConfig, UserConfigBuilder, UserConfigError};
 
@@ -10,7 +14,7 @@ mod emotes;
 pub use emotes::Emotes;
 
 mod badge;
-pub use badge::{Badge, BadgeInfo, BadgeKind};
+pub use badge::{Badge, BadgeInfo, BadgeKind, PredictionChoice};
 
 pub mod color;
 #[doc(inline)]
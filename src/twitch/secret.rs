@@ -0,0 +1,304 @@
+//! A redacting string type for credentials, such as OAuth tokens.
+
+/**
+A string that shouldn't be shown to anyone -- e.g. an OAuth token.
+
+Both the [Debug](std::fmt::Debug) and [Display](std::fmt::Display) implementations redact the value,
+so it is safe to print a type that contains one of these (e.g. a [UserConfig](super::UserConfig)).
+
+The inner string is zeroed out when this is dropped.
+
+```
+# use trovochat::trovo::Secret;
+let secret = Secret::new("oauth:hunter2");
+assert_eq!(format!("{:?}", secret), "<redacted>");
+assert_eq!(secret.to_string(), "<redacted>");
+
+// you have to explicitly ask for the value
+assert_eq!(secret.expose(), "oauth:hunter2");
+```
+
+# Serialization
+With the `serde` feature enabled, this serializes as a redacted string by default.
+
+To serialize the actual value, you have to opt-in with [Secret::exposed()] or the [serialize_exposed] function:
+```ignore
+# use trovochat::trovo::Secret;
+#[derive(serde::Serialize)]
+struct Config {
+    // this is written out as "<redacted>"
+    redacted: Secret,
+    // this is written out as the actual value
+    #[serde(serialize_with = "trovochat::trovo::secret::serialize_exposed")]
+    exposed: Secret,
+}
+```
+
+Deserializing a redacted string will produce an error, rather than a useless [Secret].
+
+[serialize_exposed]: crate::trovo::secret::serialize_exposed
+*/
+#[derive(Clone, PartialEq, Eq)]
+pub struct Secret(String);
+
+impl Secret {
+    /// What is shown in place of the actual value
+    pub const REDACTED: &'static str = "<redacted>";
+
+    /// Create a new secret from this string
+    pub fn new(secret: impl Into<String>) -> Self {
+        Self(secret.into())
+    }
+
+    /// Get the actual value of this secret
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    /// Get a wrapper that'll serialize the actual value of this secret
+    pub fn exposed(&self) -> Exposed<'_> {
+        Exposed(self)
+    }
+
+    /// Checks whether the secret is empty
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Gets the length of the secret, in bytes
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    // zero out the whole buffer in place, including any spare capacity
+    #[allow(unsafe_code)]
+    fn zero(&mut self) {
+        // SAFETY: only zeros are written, which is valid UTF-8, and every write is within the allocation
+        unsafe {
+            let data = self.0.as_mut_vec();
+            let ptr = data.as_mut_ptr();
+            for i in 0..data.capacity() {
+                // volatile so the writes can't be removed, even though the buffer is about to be freed
+                std::ptr::write_volatile(ptr.add(i), 0);
+            }
+        }
+        std::sync::atomic::compiler_fence(std::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        self.zero();
+    }
+}
+
+impl std::fmt::Debug for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(Self::REDACTED)
+    }
+}
+
+impl std::fmt::Display for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(Self::REDACTED)
+    }
+}
+
+impl From<String> for Secret {
+    fn from(secret: String) -> Self {
+        Self(secret)
+    }
+}
+
+impl From<&String> for Secret {
+    fn from(secret: &String) -> Self {
+        Self(secret.clone())
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(secret: &str) -> Self {
+        Self(secret.to_string())
+    }
+}
+
+impl From<Box<str>> for Secret {
+    fn from(secret: Box<str>) -> Self {
+        Self(secret.into())
+    }
+}
+
+/// A borrowed [Secret] that serializes its actual value. Obtained from [Secret::exposed()]
+#[derive(Copy, Clone)]
+pub struct Exposed<'a>(&'a Secret);
+
+impl<'a> std::fmt::Debug for Exposed<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl ::serde::Serialize for Secret {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ::serde::Serializer,
+    {
+        serializer.serialize_str(Self::REDACTED)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'a> ::serde::Serialize for Exposed<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ::serde::Serializer,
+    {
+        serializer.serialize_str(self.0.expose())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> ::serde::Deserialize<'de> for Secret {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: ::serde::Deserializer<'de>,
+    {
+        let secret = String::deserialize(deserializer).map(Self)?;
+        if secret.expose() == Self::REDACTED {
+            return Err(::serde::de::Error::custom(
+                "cannot deserialize a redacted secret",
+            ));
+        }
+        Ok(secret)
+    }
+}
+
+/// Serializes the actual value of the [Secret]
+///
+/// This is meant to be used with `#[serde(serialize_with = "...")]`
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub fn serialize_exposed<S>(secret: &Secret, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: ::serde::Serializer,
+{
+    ::serde::Serialize::serialize(&secret.exposed(), serializer)
+}
+
+/// Masks the value of any `PASS` command found in these lines
+pub(crate) fn mask_pass(data: &str) -> std::borrow::Cow<'_, str> {
+    if data.split('\n').all(|line| pass_value(line).is_none()) {
+        return data.into();
+    }
+
+    let mut buf = String::with_capacity(data.len());
+    for (i, line) in data.split('\n').enumerate() {
+        if i > 0 {
+            buf.push('\n');
+        }
+
+        match pass_value(line) {
+            Some(pos) => {
+                buf.push_str(&line[..pos]);
+                buf.push_str(Secret::REDACTED);
+                buf.push_str(&line[line.trim_end().len()..]);
+            }
+            None => buf.push_str(line),
+        }
+    }
+    buf.into()
+}
+
+// where the value starts, if this line is a `PASS` command. this skips any tags and prefix
+fn pass_value(line: &str) -> Option<usize> {
+    let spaces = |s: &str| s.len() - s.trim_start_matches(' ').len();
+
+    let mut pos = 0;
+    for sigil in &['@', ':'] {
+        if line[pos..].starts_with(*sigil) {
+            pos += line[pos..].find(' ')?;
+            pos += spaces(&line[pos..]);
+        }
+    }
+
+    if !line[pos..].get(..5)?.eq_ignore_ascii_case("PASS ") {
+        return None;
+    }
+    pos += 5;
+    Some(pos + spaces(&line[pos..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacted() {
+        let secret = Secret::new("oauth:hunter2");
+        assert_eq!(format!("{:?}", secret), Secret::REDACTED);
+        assert_eq!(format!("{}", secret), Secret::REDACTED);
+        assert_eq!(format!("{:?}", secret.exposed()), Secret::REDACTED);
+        assert_eq!(secret.expose(), "oauth:hunter2");
+    }
+
+    #[test]
+    fn zeroed() {
+        let mut secret = Secret::new(String::with_capacity(32) + "oauth:hunter2");
+        secret.zero();
+        assert_eq!(secret.expose(), "\0".repeat("oauth:hunter2".len()));
+    }
+
+    #[test]
+    fn mask_pass_lines() {
+        let input = "CAP REQ :trovo.tv/tags\r\nPASS oauth:hunter2\r\nNICK museun\r\n";
+        assert_eq!(
+            mask_pass(input),
+            "CAP REQ :trovo.tv/tags\r\nPASS <redacted>\r\nNICK museun\r\n"
+        );
+
+        let input = "@+client= PASS oauth:hunter2\r\n@+client= NICK museun\r\n";
+        assert_eq!(
+            mask_pass(input),
+            "@+client= PASS <redacted>\r\n@+client= NICK museun\r\n"
+        );
+
+        let input = "@a=b :museun PASS oauth:hunter2\r\n";
+        assert_eq!(mask_pass(input), "@a=b :museun PASS <redacted>\r\n");
+
+        let input = "pass oauth:hunter2\r\n";
+        assert_eq!(mask_pass(input), "pass <redacted>\r\n");
+
+        let input = "PRIVMSG #museun :PASS oauth:hunter2\r\n";
+        assert_eq!(mask_pass(input), input);
+
+        let input = "@a=b PRIVMSG #museun :pass oauth:hunter2\r\n";
+        assert_eq!(mask_pass(input), input);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_opt_in() {
+        #[derive(::serde::Serialize)]
+        struct Config {
+            redacted: Secret,
+            #[serde(serialize_with = "serialize_exposed")]
+            exposed: Secret,
+        }
+
+        let config = Config {
+            redacted: Secret::new("oauth:hunter2"),
+            exposed: Secret::new("oauth:hunter2"),
+        };
+
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(
+            json,
+            r#"{"redacted":"<redacted>","exposed":"oauth:hunter2"}"#
+        );
+
+        let secret: Secret = serde_json::from_str(r#""oauth:hunter2""#).unwrap();
+        assert_eq!(secret.expose(), "oauth:hunter2");
+        assert!(serde_json::from_str::<Secret>(r#""<redacted>""#).is_err());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::trovo::Capability;
+use crate::trovo::{Capability, Secret};
 use std::collections::BTreeSet;
 
 /**
@@ -32,9 +32,27 @@ pub struct UserConfig {
     /// Requested name of your user
     pub name: String,
     /// OAuth token of the user
-    pub token: String,
+    ///
+    /// This is redacted when printed or serialized. See [Secret] for more information.
+    pub token: Secret,
     /// Capabilities to be requested from the server
+    ///
+    /// If the server rejects any of these, connecting will fail.
     pub capabilities: Vec<Capability>,
+    /// Capabilities to be requested from the server, which are allowed to be rejected
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub optional_capabilities: Vec<Capability>,
+    /// Any other capabilities to be requested from the server, by name. e.g. `example.com/foo`
+    ///
+    /// If the server rejects any of these, connecting will fail.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub custom_capabilities: Vec<String>,
+    /// Any other capabilities to be requested from the server by name, which are allowed to be rejected
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub optional_custom_capabilities: Vec<String>,
+    /// Whether to ask the server which capabilities are available, with `CAP LS`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub list_capabilities: bool,
 }
 
 impl UserConfig {
@@ -45,7 +63,29 @@ impl UserConfig {
 
     /// Determines whether this config was requested as anonymous
     pub fn is_anonymous(&self) -> bool {
-        self.name == crate::JUSTINFAN1234 && self.token == crate::JUSTINFAN1234
+        self.name == crate::JUSTINFAN1234 && self.token.expose() == crate::JUSTINFAN1234
+    }
+
+    /// The names of every capability that will be requested, both required and optional
+    pub fn requested_capabilities(&self) -> impl Iterator<Item = &str> + '_ {
+        self.capabilities
+            .iter()
+            .chain(&self.optional_capabilities)
+            .map(|cap| cap.as_str())
+            .chain(
+                self.custom_capabilities
+                    .iter()
+                    .chain(&self.optional_custom_capabilities)
+                    .map(|name| name.as_str()),
+            )
+    }
+
+    /// Whether the capability with this name is required, rather than optional
+    pub fn is_required(&self, name: &str) -> bool {
+        match Capability::maybe_from_str(name) {
+            Some(cap) => self.capabilities.contains(&cap),
+            None => self.custom_capabilities.iter().any(|s| s == name),
+        }
     }
 }
 
@@ -82,8 +122,12 @@ impl std::error::Error for UserConfigError {}
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct UserConfigBuilder {
     capabilities: BTreeSet<Capability>,
+    optional_capabilities: BTreeSet<Capability>,
+    custom_capabilities: BTreeSet<String>,
+    optional_custom_capabilities: BTreeSet<String>,
+    list_capabilities: bool,
     name: Option<String>,
-    token: Option<String>,
+    token: Option<Secret>,
 }
 
 impl UserConfigBuilder {
@@ -98,8 +142,8 @@ impl UserConfigBuilder {
     /// This is an oauth token. It must have atleast two [scopes]: `chat:read`, `chat:edit`
     ///
     /// [scopes]: https://dev.trovo.tv/docs/authentication/#scopes
-    pub fn token(mut self, token: impl ToString) -> Self {
-        self.token.replace(token.to_string());
+    pub fn token(mut self, token: impl Into<Secret>) -> Self {
+        self.token.replace(token.into());
         self
     }
 
@@ -115,7 +159,58 @@ impl UserConfigBuilder {
     /// Capabilities to enable
     ///
     pub fn capabilities(mut self, caps: &[Capability]) -> Self {
-        self.capabilities.extend(caps);
+        self.capabilities.extend(caps.iter().copied());
+        self
+    }
+
+    /// Capabilities to enable, if the server supports them
+    ///
+    /// If the server rejects any of these, the connection will continue without them.
+    pub fn optional_capabilities(mut self, caps: &[Capability]) -> Self {
+        self.optional_capabilities.extend(caps.iter().copied());
+        self
+    }
+
+    /// Any other capabilities to enable, by name. e.g. `example.com/foo`
+    ///
+    /// A name of one of the [Capability]s is the same as enabling it with [UserConfigBuilder::capabilities].
+    pub fn custom_capabilities(mut self, names: &[&str]) -> Self {
+        for name in names {
+            match Capability::maybe_from_str(name) {
+                Some(cap) => {
+                    self.capabilities.insert(cap);
+                }
+                None => {
+                    self.custom_capabilities.insert(name.to_string());
+                }
+            }
+        }
+        self
+    }
+
+    /// Any other capabilities to enable by name, if the server supports them
+    ///
+    /// If the server rejects any of these, the connection will continue without them.
+    pub fn optional_custom_capabilities(mut self, names: &[&str]) -> Self {
+        for name in names {
+            match Capability::maybe_from_str(name) {
+                Some(cap) => {
+                    self.optional_capabilities.insert(cap);
+                }
+                None => {
+                    self.optional_custom_capabilities.insert(name.to_string());
+                }
+            }
+        }
+        self
+    }
+
+    /// Ask the server which capabilities are available
+    ///
+    /// This sends a `CAP LS` before registering. The [AsyncRunner](crate::AsyncRunner) ends the negotiation with a `CAP END`
+    /// once the server has listed its capabilities and answered every request.
+    pub fn list_capabilities(mut self) -> Self {
+        self.list_capabilities = true;
         self
     }
 
@@ -147,10 +242,10 @@ impl UserConfigBuilder {
 
         let token = self
             .token
-            .filter(|s| validate_token(s))
+            .filter(|s| validate_token(s.expose()))
             .ok_or(UserConfigError::InvalidToken)?;
 
-        match (name.as_str(), token.as_str()) {
+        match (name.as_str(), token.expose()) {
             (crate::JUSTINFAN1234, crate::JUSTINFAN1234) => {
                 // both are allowed
             }
@@ -160,10 +255,28 @@ impl UserConfigBuilder {
             _ => {}
         }
 
+        let capabilities = self.capabilities;
+        let optional_capabilities = self
+            .optional_capabilities
+            .into_iter()
+            .filter(|cap| !capabilities.contains(cap))
+            .collect();
+
+        let custom_capabilities = self.custom_capabilities;
+        let optional_custom_capabilities = self
+            .optional_custom_capabilities
+            .into_iter()
+            .filter(|name| !custom_capabilities.contains(name))
+            .collect();
+
         Ok(UserConfig {
             name,
             token,
-            capabilities: self.capabilities.into_iter().collect(),
+            capabilities: capabilities.into_iter().collect(),
+            optional_capabilities,
+            custom_capabilities: custom_capabilities.into_iter().collect(),
+            optional_custom_capabilities,
+            list_capabilities: self.list_capabilities,
         })
     }
 }
@@ -197,8 +310,12 @@ mod tests {
             config,
             UserConfig {
                 name: "foo".to_string(),
-                token: format!("oauth:{}", "a".repeat(30)),
+                token: format!("oauth:{}", "a".repeat(30)).into(),
                 capabilities: vec![],
+                optional_capabilities: vec![],
+                custom_capabilities: vec![],
+                optional_custom_capabilities: vec![],
+                list_capabilities: false,
             }
         )
     }
@@ -217,12 +334,52 @@ mod tests {
             config,
             UserConfig {
                 name: "foo".to_string(),
-                token: format!("oauth:{}", "a".repeat(30)),
+                token: format!("oauth:{}", "a".repeat(30)).into(),
                 capabilities: vec![Capability::Membership, Capability::Tags,],
+                optional_capabilities: vec![],
+                custom_capabilities: vec![],
+                optional_custom_capabilities: vec![],
+                list_capabilities: false,
             }
         )
     }
 
+    #[test]
+    fn valid_user_config_optional_caps() {
+        let config = UserConfig::builder()
+            .name("foo")
+            .token(format!("oauth:{}", "a".repeat(30)))
+            .capabilities(&[Capability::Tags])
+            .optional_capabilities(&[Capability::Tags, Capability::Membership])
+            .custom_capabilities(&["example.com/foo"])
+            .optional_custom_capabilities(&[
+                "example.com/foo",
+                "example.com/bar",
+                "trovo.tv/commands",
+            ])
+            .list_capabilities()
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            config,
+            UserConfig {
+                name: "foo".to_string(),
+                token: format!("oauth:{}", "a".repeat(30)).into(),
+                capabilities: vec![Capability::Tags],
+                optional_capabilities: vec![Capability::Membership, Capability::Commands],
+                custom_capabilities: vec!["example.com/foo".to_string()],
+                optional_custom_capabilities: vec!["example.com/bar".to_string()],
+                list_capabilities: true,
+            }
+        );
+
+        assert!(config.is_required("example.com/foo"));
+        assert!(!config.is_required("example.com/bar"));
+        assert!(!config.is_required("trovo.tv/membership"));
+        assert_eq!(config.requested_capabilities().count(), 5);
+    }
+
     #[test]
     fn valid_user_config_anonymous() {
         let config = UserConfig::builder().anonymous().build().unwrap();
@@ -231,14 +388,31 @@ mod tests {
             config,
             UserConfig {
                 name: crate::JUSTINFAN1234.to_string(),
-                token: crate::JUSTINFAN1234.to_string(),
+                token: crate::JUSTINFAN1234.into(),
                 capabilities: vec![],
+                optional_capabilities: vec![],
+                custom_capabilities: vec![],
+                optional_custom_capabilities: vec![],
+                list_capabilities: false,
             }
         );
 
         assert!(config.is_anonymous());
     }
 
+    #[test]
+    fn user_config_debug_redacted() {
+        let token = format!("oauth:{}", "a".repeat(30));
+        let config = UserConfig::builder()
+            .name("foo")
+            .token(&token)
+            .build()
+            .unwrap();
+
+        assert_eq!(config.token.expose(), token);
+        assert!(!format!("{:?}", config).contains(&token));
+    }
+
     #[test]
     fn invalid_name_missing() {
         let err = UserConfig::builder().build().unwrap_err();
@@ -0,0 +1,419 @@
+/*!
+Derive macros for [trovochat](https://docs.rs/trovochat)
+
+This is re-exported by `trovochat` when its `derive` feature is enabled, which is how it should be used.
+*/
+#![deny(missing_docs, missing_debug_implementations, unused_qualifications)]
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    spanned::Spanned as _, Attribute, Data, DeriveInput, Error, Fields, Ident, Lifetime, Lit,
+    LitStr, Meta, NestedMeta, PathArguments, Type, TypePath,
+};
+
+/**
+Derive `FromIrcMessage`, `IntoOwned` and accessors for a message type
+
+See the `trovochat::FromIrcMessage` derive docs for the attributes that can be used.
+*/
+#[proc_macro_derive(FromIrcMessage, attributes(irc))]
+pub fn derive_from_irc_message(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+// what part of the message a field comes from
+enum Kind {
+    Raw,
+    Tags,
+    Nick,
+    Arg(usize),
+    Data,
+    Tag(LitStr),
+}
+
+struct Field<'a> {
+    ident: &'a Ident,
+    ty: &'a Type,
+    kind: Kind,
+    // whether the field is an `Option<T>`
+    optional: bool,
+    docs: Vec<&'a Attribute>,
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let lifetime = single_lifetime(input)?;
+    let command = command(input)?;
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => fields
+                .named
+                .iter()
+                .map(Field::parse)
+                .collect::<syn::Result<Vec<_>>>()?,
+            _ => {
+                return Err(Error::new(
+                    input.span(),
+                    "expected a struct with named fields",
+                ))
+            }
+        },
+        _ => return Err(Error::new(input.span(), "expected a struct")),
+    };
+
+    let mut raw = fields
+        .iter()
+        .filter(|field| matches!(field.kind, Kind::Raw));
+    let raw = match (raw.next(), raw.next()) {
+        (Some(raw), None) => raw.ident,
+        (_, Some(field)) => return Err(Error::new(field.ident.span(), "duplicate `raw` field")),
+        (None, None) => {
+            return Err(Error::new(
+                input.span(),
+                "expected a `raw: MaybeOwned<'a>` field",
+            ))
+        }
+    };
+    let tags = fields
+        .iter()
+        .find(|field| matches!(field.kind, Kind::Tags))
+        .map(|field| field.ident);
+
+    let from_irc = from_irc(name, &lifetime, &command, raw, &fields);
+    let into_owned = into_owned(name, &lifetime, &fields);
+    let accessors = accessors(name, &lifetime, raw, tags, &fields);
+    let serde = serde(name, &lifetime, &fields);
+
+    Ok(quote! {
+        #accessors
+        #from_irc
+        #into_owned
+        #serde
+    })
+}
+
+fn single_lifetime(input: &DeriveInput) -> syn::Result<Lifetime> {
+    let generics = &input.generics;
+    let mut lifetimes = generics.lifetimes();
+    match (lifetimes.next(), lifetimes.next()) {
+        (Some(def), None) if generics.params.len() == 1 => Ok(def.lifetime.clone()),
+        _ => Err(Error::new(
+            generics.span(),
+            "expected a single lifetime parameter, e.g. `struct Foo<'a>`",
+        )),
+    }
+}
+
+fn command(input: &DeriveInput) -> syn::Result<LitStr> {
+    let mut command = None;
+    for meta in irc_attrs(&input.attrs)? {
+        let lit = match meta {
+            Meta::NameValue(nv) if nv.path.is_ident("command") => match nv.lit {
+                Lit::Str(lit) => lit,
+                lit => return Err(Error::new(lit.span(), "expected a string")),
+            },
+            meta => return Err(Error::new(meta.span(), "unknown attribute")),
+        };
+        if command.replace(lit).is_some() {
+            return Err(Error::new(input.span(), "duplicate `command` attribute"));
+        }
+    }
+    command.ok_or_else(|| {
+        Error::new(
+            input.span(),
+            "expected a `#[irc(command = \"...\")]` attribute",
+        )
+    })
+}
+
+// the items inside of any `#[irc(...)]` attributes
+fn irc_attrs(attrs: &[Attribute]) -> syn::Result<Vec<Meta>> {
+    let mut out = vec![];
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("irc")) {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => return Err(Error::new(meta.span(), "expected `#[irc(...)]`")),
+        };
+        for nested in list.nested {
+            match nested {
+                NestedMeta::Meta(meta) => out.push(meta),
+                NestedMeta::Lit(lit) => return Err(Error::new(lit.span(), "unexpected literal")),
+            }
+        }
+    }
+    Ok(out)
+}
+
+impl<'a> Field<'a> {
+    fn parse(field: &'a syn::Field) -> syn::Result<Self> {
+        let ident = field.ident.as_ref().expect("named field");
+
+        let mut kind = None;
+        for meta in irc_attrs(&field.attrs)? {
+            let next = match &meta {
+                Meta::Path(path) if path.is_ident("raw") => Kind::Raw,
+                Meta::Path(path) if path.is_ident("tags") => Kind::Tags,
+                Meta::Path(path) if path.is_ident("nick") => Kind::Nick,
+                Meta::Path(path) if path.is_ident("data") => Kind::Data,
+                Meta::NameValue(nv) if nv.path.is_ident("arg") => match &nv.lit {
+                    Lit::Int(lit) => Kind::Arg(lit.base10_parse()?),
+                    lit => return Err(Error::new(lit.span(), "expected an integer")),
+                },
+                Meta::NameValue(nv) if nv.path.is_ident("tag") => match &nv.lit {
+                    Lit::Str(lit) => Kind::Tag(lit.clone()),
+                    lit => return Err(Error::new(lit.span(), "expected a string")),
+                },
+                meta => return Err(Error::new(meta.span(), "unknown attribute")),
+            };
+            if kind.replace(next).is_some() {
+                return Err(Error::new(meta.span(), "a field can only have one kind"));
+            }
+        }
+
+        // like the hand-written messages, `raw` and `tags` don't need an attribute
+        let kind = match kind {
+            Some(kind) => kind,
+            None if ident == "raw" => Kind::Raw,
+            None if ident == "tags" => Kind::Tags,
+            None => {
+                return Err(Error::new(
+                    ident.span(),
+                    "expected one of `#[irc(nick)]`, `#[irc(arg = N)]`, `#[irc(data)]` or `#[irc(tag = \"...\")]`",
+                ))
+            }
+        };
+
+        Ok(Self {
+            ident,
+            ty: &field.ty,
+            kind,
+            optional: is_option(&field.ty),
+            docs: field
+                .attrs
+                .iter()
+                .filter(|attr| attr.path.is_ident("doc"))
+                .collect(),
+        })
+    }
+}
+
+fn is_option(ty: &Type) -> bool {
+    let path = match ty {
+        Type::Path(TypePath { qself: None, path }) => path,
+        _ => return false,
+    };
+    matches!(
+        path.segments.last(),
+        Some(segment) if segment.ident == "Option"
+            && matches!(segment.arguments, PathArguments::AngleBracketed(..))
+    )
+}
+
+fn from_irc(
+    name: &Ident,
+    lifetime: &Lifetime,
+    command: &LitStr,
+    raw: &Ident,
+    fields: &[Field<'_>],
+) -> TokenStream2 {
+    let needs_tags = fields
+        .iter()
+        .any(|field| matches!(field.kind, Kind::Tags | Kind::Tag(..)));
+    let parse_tags = if needs_tags {
+        quote! { let __tags = ::trovochat::Validator::parse_tags(&msg); }
+    } else {
+        quote! {}
+    };
+
+    // typed tags borrow the message, so they are parsed before anything is moved
+    let typed_tags = fields.iter().filter_map(|field| {
+        let ident = field.ident;
+        let tag = match &field.kind {
+            Kind::Tag(tag) => tag,
+            _ => return None,
+        };
+        let func = if field.optional {
+            quote! { optional_tag }
+        } else {
+            quote! { required_tag }
+        };
+        Some(quote! {
+            let #ident = ::trovochat::derive::#func(&msg.raw, &__tags, #tag)?;
+        })
+    });
+
+    let values = fields.iter().filter_map(|field| {
+        let ident = field.ident;
+        let value = match (&field.kind, field.optional) {
+            (Kind::Raw, _) => return None,
+            (Kind::Tags, _) => quote! { __tags },
+            (Kind::Tag(..), _) => quote! { #ident },
+            (Kind::Nick, false) => quote! { ::trovochat::Validator::expect_nick(&msg)? },
+            (Kind::Nick, true) => quote! { msg.prefix.and_then(|p| p.nick_index()) },
+            (Kind::Arg(nth), false) => {
+                quote! { ::trovochat::Validator::expect_arg_index(&msg, #nth)? }
+            }
+            (Kind::Arg(nth), true) => quote! { msg.nth_arg_index(#nth) },
+            (Kind::Data, false) => quote! { ::trovochat::Validator::expect_data_index(&msg)? },
+            (Kind::Data, true) => quote! { msg.data },
+        };
+        Some(quote! { #ident: #value, })
+    });
+
+    quote! {
+        impl<#lifetime> ::trovochat::FromIrcMessage<#lifetime> for #name<#lifetime> {
+            type Error = ::trovochat::MessageError;
+
+            fn from_irc(msg: ::trovochat::IrcMessage<#lifetime>) -> Result<Self, Self::Error> {
+                ::trovochat::Validator::expect_command(&msg, #command)?;
+                #parse_tags
+                #(#typed_tags)*
+
+                let this = Self {
+                    #(#values)*
+                    #raw: msg.raw,
+                };
+
+                Ok(this)
+            }
+
+            fn into_inner(self) -> ::trovochat::maybe_owned::MaybeOwned<#lifetime> {
+                self.#raw
+            }
+        }
+    }
+}
+
+fn into_owned(name: &Ident, lifetime: &Lifetime, fields: &[Field<'_>]) -> TokenStream2 {
+    let fields = fields.iter().map(|field| {
+        let ident = field.ident;
+        match field.kind {
+            // typed tags are already owned
+            Kind::Tag(..) => quote! { #ident: self.#ident, },
+            _ => quote! { #ident: ::trovochat::IntoOwned::into_owned(self.#ident), },
+        }
+    });
+
+    quote! {
+        impl<#lifetime> ::trovochat::IntoOwned<#lifetime> for #name<#lifetime> {
+            type Output = #name<'static>;
+
+            fn into_owned(self) -> Self::Output {
+                #name { #(#fields)* }
+            }
+        }
+    }
+}
+
+fn accessors(
+    name: &Ident,
+    lifetime: &Lifetime,
+    raw: &Ident,
+    tags: Option<&Ident>,
+    fields: &[Field<'_>],
+) -> TokenStream2 {
+    let tags = tags.map(|tags| {
+        quote! {
+            /// Get a view of parsable tags
+            pub fn tags(&self) -> ::trovochat::irc::Tags<'_> {
+                ::trovochat::irc::Tags::from_data_indices(&self.#raw, &self.#tags)
+            }
+        }
+    });
+
+    let accessors = fields.iter().filter_map(|field| {
+        let ident = field.ident;
+        let ty = field.ty;
+        let docs = &field.docs;
+        let body = match (&field.kind, field.optional) {
+            (Kind::Raw, _) | (Kind::Tags, _) => return None,
+            (Kind::Tag(..), _) => quote! {
+                pub fn #ident(&self) -> #ty {
+                    ::std::clone::Clone::clone(&self.#ident)
+                }
+            },
+            (_, false) => quote! {
+                pub fn #ident(&self) -> &str {
+                    &self.#raw[self.#ident]
+                }
+            },
+            (_, true) => quote! {
+                pub fn #ident(&self) -> Option<&str> {
+                    self.#ident.map(|index| &self.#raw[index])
+                }
+            },
+        };
+        Some(quote! {
+            #(#docs)*
+            #body
+        })
+    });
+
+    quote! {
+        impl<#lifetime> #name<#lifetime> {
+            /// Get the raw message
+            pub fn raw(&self) -> &str {
+                &*self.#raw
+            }
+
+            /// Re-encode this message in its canonical form, optionally rewriting its tags or prefix
+            pub fn reencode(&self) -> ::trovochat::irc::Reencode<'_> {
+                ::trovochat::irc::Reencode::new(&*self.#raw)
+            }
+
+            #tags
+            #(#accessors)*
+        }
+    }
+}
+
+// this is wrapped in a macro from trovochat, so it only exists if trovochat has its `serde` feature enabled
+fn serde(name: &Ident, lifetime: &Lifetime, fields: &[Field<'_>]) -> TokenStream2 {
+    let entries = fields.iter().map(|field| {
+        let ident = field.ident;
+        let key = match field.kind {
+            Kind::Raw => "raw".to_string(),
+            Kind::Tags => "tags".to_string(),
+            _ => ident.to_string(),
+        };
+        let key = LitStr::new(&key, ident.span());
+        let accessor = match field.kind {
+            Kind::Raw => quote! { raw },
+            Kind::Tags => quote! { tags },
+            _ => quote! { #ident },
+        };
+        quote! { s.serialize_entry(#key, &self.#accessor())?; }
+    });
+    let len = fields.len();
+
+    quote! {
+        ::trovochat::__cfg_serde! {
+            impl<#lifetime> ::trovochat::derive::serde::Serialize for #name<#lifetime> {
+                fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                where
+                    S: ::trovochat::derive::serde::Serializer,
+                {
+                    use ::trovochat::derive::serde::ser::SerializeMap as _;
+                    let mut s = serializer.serialize_map(Some(#len))?;
+                    #(#entries)*
+                    s.end()
+                }
+            }
+
+            impl<'de, #lifetime> ::trovochat::derive::serde::Deserialize<'de> for #name<#lifetime> {
+                fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                where
+                    D: ::trovochat::derive::serde::Deserializer<'de>,
+                {
+                    deserializer.deserialize_map(::trovochat::derive::RawVisitor::default())
+                }
+            }
+        }
+    }
+}